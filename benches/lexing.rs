@@ -0,0 +1,73 @@
+//! lexing long expressions, and unit name lookups through the registry index compared to
+//! scanning every unit like the lexer used to
+//!
+//! run with `cargo bench --bench lexing`
+#![allow(dead_code)]
+#[path = "../src/clipboard.rs"]
+mod clipboard;
+#[path = "../src/query_manager.rs"]
+mod query_manager;
+#[path = "../src/unit_calc_parser/mod.rs"]
+mod unit_calc_parser;
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use unit_calc_parser::{
+    environment::Environment,
+    lexer::{get_units, lex_all},
+};
+
+const EXPRESSIONS: &[&str] = &[
+    "1 nm + 4 am + 3 km + 1 in in cm",
+    "1 nm + 4 am + 3 km + 1 in + 5 mi + 3 ft + 2 yd + 7 mm + 12 cm + 3 dm in cm",
+    "(3 kg * 9.81 m/s^2 + 12 N) * 4 h / (2 min + 30 s) + 5 kW * 3 h in kJ",
+];
+const ITERATIONS: u32 = 20;
+
+fn time(mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+/// every substring the lexer might look up
+fn substrings(s: &str) -> Vec<String> {
+    let chars = s.chars().collect::<Vec<char>>();
+    (0..chars.len())
+        .flat_map(|start| (start + 1..=chars.len()).map(move |end| (start, end)))
+        .map(|(start, end)| chars[start..end].iter().collect())
+        .collect()
+}
+fn main() {
+    let units = get_units();
+    let env = Environment::default();
+    println!("{} units", units.len());
+    for expression in EXPRESSIONS {
+        println!("{expression}");
+        let lexing = time(|| {
+            black_box(lex_all(expression.to_string(), &units, &env));
+        });
+        let words = substrings(expression);
+        let indexed = time(|| {
+            for w in &words {
+                black_box(units.lookup(w).count());
+            }
+        });
+        let scanned = time(|| {
+            for w in &words {
+                black_box(units.iter().filter(|u| u.valid_names.contains(w)).count());
+            }
+        });
+        println!("  lex_all          {lexing:>12.2?}");
+        println!("  indexed lookups  {indexed:>12.2?}");
+        println!("  scanned lookups  {scanned:>12.2?}");
+        println!(
+            "  speedup          {:>11.0}x",
+            scanned.as_secs_f64() / indexed.as_secs_f64()
+        );
+    }
+}
@@ -58,7 +58,7 @@ impl Default for AppParser {
                         if let Ok(entries) = fs::read_dir(dir) {
                             for entry in entries.flatten() {
                                 let path = entry.path();
-                                if path.extension().map_or(false, |ext| ext == "desktop") {
+                                if path.extension().is_some_and(|ext| ext == "desktop") {
                                     use tokio::{fs::File, io::AsyncReadExt};
 
                                     let name =
@@ -96,7 +96,7 @@ impl Default for AppParser {
 impl QueryParser for AppParser {
     async fn parse(&self, query: String, resopnse: mpsc::Sender<ListEntry>) {
         let mut apps = self.apps.read().await;
-        while apps.len() == 0 {
+        while apps.is_empty() {
             drop(apps);
             tokio::time::sleep(std::time::Duration::from_millis(100)).await;
             apps = self.apps.read().await;
@@ -117,7 +117,7 @@ impl QueryParser for AppParser {
             resopnse
                 .send(ListEntry {
                     layout_fn: Box::new(move |ui| {
-                        ui.label(format!("{}", &s2.name));
+                        ui.label(&s2.name);
                     }),
                     execute: Some(Box::new(move || {
                         #[cfg(target_os = "windows")]
@@ -127,6 +127,8 @@ impl QueryParser for AppParser {
                         }
                         #[cfg(target_os = "linux")]
                         {
+                            // the launcher exits right away, the program is meant to outlive it
+                            #[allow(clippy::zombie_processes)]
                             let _ = Command::new("bash")
                                 .arg("-c")
                                 .arg(s3.app_i_d.clone())
@@ -139,6 +141,7 @@ impl QueryParser for AppParser {
                         std::process::exit(0);
                     })),
                     priority,
+                    fill_query: None,
                 })
                 .await
                 .unwrap();
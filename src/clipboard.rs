@@ -0,0 +1,26 @@
+use std::sync::Mutex;
+
+/// why the last copy failed, shown under the search field until the query changes
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+/// puts `text` on the system clipboard
+pub fn copy(text: &str) -> Result<(), String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| e.to_string())
+}
+/// copies `text`, keeping the reason for `last_error` if the clipboard is unavailable
+pub fn copy_or_report(text: &str) {
+    let error = copy(text).err().map(|e| format!("could not copy to the clipboard: {e}"));
+    if let Some(e) = &error {
+        eprintln!("{e}");
+    }
+    *LAST_ERROR.lock().unwrap() = error;
+}
+/// the reason the last copy failed, `None` if it worked
+pub fn last_error() -> Option<String> {
+    LAST_ERROR.lock().unwrap().clone()
+}
+pub fn clear_error() {
+    *LAST_ERROR.lock().unwrap() = None;
+}
@@ -42,7 +42,7 @@ impl Default for CustomCommandsParser {
                     }
                     #[cfg(target_os = "linux")]
                     {
-                        skip |= vec!["bat", "exe", "ps1"]
+                        skip |= ["bat", "exe", "ps1"]
                             .contains(&sc.path().extension().unwrap().to_str().unwrap());
                     }
                     if !skip {
@@ -71,7 +71,7 @@ impl Default for CustomCommandsParser {
 impl QueryParser for CustomCommandsParser {
     async fn parse(&self, query: String, resopnse: mpsc::Sender<ListEntry>) {
         let mut scripts = self.scripts.read().await;
-        while scripts.len() == 0 {
+        while scripts.is_empty() {
             drop(scripts);
             tokio::time::sleep(std::time::Duration::from_millis(100)).await;
             scripts = self.scripts.read().await;
@@ -92,7 +92,7 @@ impl QueryParser for CustomCommandsParser {
             resopnse
                 .send(ListEntry {
                     layout_fn: Box::new(move |ui| {
-                        ui.label(format!("{}", &s2.name));
+                        ui.label(&s2.name);
                     }),
                     execute: Some(Box::new(move || {
                         #[cfg(target_os = "windows")]
@@ -105,12 +105,14 @@ impl QueryParser for CustomCommandsParser {
 
                             match Path::new(&s3.path)
                                 .extension()
-                                .unwrap_or(&OsStr::from_bytes(b""))
+                                .unwrap_or(OsStr::from_bytes(b""))
                                 .to_str()
                                 .unwrap()
                             {
                                 "" | "sh" => {
 
+                                    // the launcher exits right away, the program is meant to outlive it
+                                    #[allow(clippy::zombie_processes)]
                                     let _ = Command::new("bash")
                                         .arg("-c")
                                         .arg(s3.path.clone())
@@ -127,6 +129,8 @@ impl QueryParser for CustomCommandsParser {
                                     File::open(&s3.path).unwrap().read_to_string(&mut content).unwrap();
                                     let arg = content[(content.find("=").unwrap()+1)..].to_string().trim().to_string();
                                     println!("{}",arg);
+                                    // the launcher exits right away, the program is meant to outlive it
+                                    #[allow(clippy::zombie_processes)]
                                     let _ = Command::new("xdg-open")
                                         .arg(arg)
                                         .spawn()
@@ -140,6 +144,7 @@ impl QueryParser for CustomCommandsParser {
                         std::process::exit(0);
                     })),
                     priority,
+                    fill_query: None,
                 })
                 .await
                 .unwrap();
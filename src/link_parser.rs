@@ -5,12 +5,8 @@ use tokio::sync::mpsc;
 use crate::query_manager::{ListEntry, QueryParser};
 
 #[derive(Clone)]
+#[derive(Default)]
 pub struct LinkParser {}
-impl Default for LinkParser {
-    fn default() -> Self {
-        Self {}
-    }
-}
 #[async_trait]
 impl QueryParser for LinkParser {
     async fn parse(&self, query: String, resopnse: mpsc::Sender<ListEntry>) {
@@ -37,7 +33,7 @@ impl QueryParser for LinkParser {
         );
         let specifies_protocoll = r"^https?://";
         let re = Regex::new(&regex).unwrap();
-        let protocoll = Regex::new(&specifies_protocoll).unwrap();
+        let protocoll = Regex::new(specifies_protocoll).unwrap();
         let q2 = query.clone();
         if re.is_match(&q2) {
             let final_link = match protocoll.is_match(&q2) {
@@ -57,6 +53,7 @@ impl QueryParser for LinkParser {
                         std::process::exit(0);
                     })),
                     priority: 100.0,
+                    fill_query: None,
                 })
                 .await
                 .unwrap();
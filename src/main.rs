@@ -1,11 +1,14 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 pub mod app_parser;
+pub mod clipboard;
 pub mod custom_commands_parser;
 pub mod link_parser;
+pub mod numeral_parser;
 pub mod path_parser;
 pub mod query_manager;
 pub mod test_parser;
+pub mod timezone_parser;
 pub mod unicode_parser;
 pub mod unit_calc_parser;
 
@@ -20,8 +23,10 @@ use tokio::sync::mpsc;
 use crate::app_parser::AppParser;
 use crate::custom_commands_parser::CustomCommandsParser;
 use crate::link_parser::LinkParser;
+use crate::numeral_parser::NumeralParser;
 use crate::path_parser::PathParser;
 use crate::query_manager::{ChangeInstruction, ListEntry, QueryManager};
+use crate::timezone_parser::TimeZoneParser;
 use crate::unicode_parser::UnicodeParser;
 use crate::unit_calc_parser::main::UnitCalcParser;
 
@@ -61,7 +66,11 @@ impl eframe::App for SearchApp {
                         .font(FontId::new(24.0, egui::FontFamily::Proportional)),
                 );
                 resp.request_focus();
+                if let Some(e) = clipboard::last_error() {
+                    ui.colored_label(egui::Color32::LIGHT_RED, e);
+                }
                 if resp.changed() {
+                    clipboard::clear_error();
                     let q = self.query.clone();
                     let sender = self.query_sender.clone();
                     tokio::spawn(async move {
@@ -77,7 +86,7 @@ impl eframe::App for SearchApp {
                             while let Ok(l) = self.layout_receiver.try_recv() {
                                 match l {
                                     ChangeInstruction::Add(la) => {
-                                        if self.selected_id == usize::MAX && la.execute.is_some() {
+                                        if self.selected_id == usize::MAX && la.selectable() {
                                             self.selected_id = self.layout.len();
                                         }
                                         self.layout.push(la);
@@ -91,14 +100,14 @@ impl eframe::App for SearchApp {
                             self.layout
                                 .sort_by(|a, b| b.priority.partial_cmp(&a.priority).unwrap());
                             if self.selected_id != usize::MAX {
-                                while self.layout[self.selected_id].execute.is_none() {
+                                while !self.layout[self.selected_id].selectable() {
                                     self.selected_id = (self.selected_id + 1) % self.layout.len();
                                 }
                             }
                             for i in 0..self.layout.len() {
                                 let l = &mut self.layout[i];
                                 let mut brightness = 10;
-                                if l.execute.is_some() {
+                                if l.selectable() {
                                     brightness = 20;
                                     if i == self.selected_id {
                                         brightness = 50;
@@ -122,27 +131,37 @@ impl eframe::App for SearchApp {
                 if ctx.input(|i| i.key_pressed(Key::Escape)) {
                     ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                 }
-                if ctx.input(|i| i.key_pressed(Key::ArrowDown)) {
-                    if self.selected_id != usize::MAX {
+                if ctx.input(|i| i.key_pressed(Key::ArrowDown))
+                    && self.selected_id != usize::MAX
+                {
+                    self.selected_id = (self.selected_id + 1) % self.layout.len();
+                    while !self.layout[self.selected_id].selectable() {
                         self.selected_id = (self.selected_id + 1) % self.layout.len();
-                        while self.layout[self.selected_id].execute.is_none() {
-                            self.selected_id = (self.selected_id + 1) % self.layout.len();
-                        }
                     }
                 }
-                if ctx.input(|i| i.key_pressed(Key::ArrowUp)) {
-                    if self.selected_id != usize::MAX {
+                if ctx.input(|i| i.key_pressed(Key::ArrowUp))
+                    && self.selected_id != usize::MAX
+                {
+                    self.selected_id =
+                        (self.selected_id - 1 + self.layout.len()) % self.layout.len();
+                    while !self.layout[self.selected_id].selectable() {
                         self.selected_id =
                             (self.selected_id - 1 + self.layout.len()) % self.layout.len();
-                        while self.layout[self.selected_id].execute.is_none() {
-                            self.selected_id =
-                                (self.selected_id - 1 + self.layout.len()) % self.layout.len();
-                        }
                     }
                 }
-                if ctx.input(|i| i.key_pressed(Key::Enter)) {
-                    if self.selected_id != usize::MAX {
-                        (self.layout[self.selected_id].execute.as_mut().unwrap())();
+                if ctx.input(|i| i.key_pressed(Key::Enter))
+                    && self.selected_id != usize::MAX
+                {
+                    let entry = &mut self.layout[self.selected_id];
+                    match entry.fill_query.clone() {
+                        Some(q) => {
+                            self.query = q.clone();
+                            let sender = self.query_sender.clone();
+                            tokio::spawn(async move {
+                                sender.send(q).await.unwrap();
+                            });
+                        }
+                        None => (entry.execute.as_mut().unwrap())(),
                     }
                 }
             });
@@ -171,8 +190,10 @@ impl eframe::App for SearchApp {
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() {
-    let mut options = eframe::NativeOptions::default();
-    options.run_and_return = false;
+    let mut options = eframe::NativeOptions {
+        run_and_return: false,
+        ..Default::default()
+    };
     #[cfg(target_os = "windows")]
     {
         options.centered = true;
@@ -215,6 +236,8 @@ async fn main() {
         mgr.add_query_parser::<LinkParser>();
         mgr.add_query_parser::<PathParser>();
         mgr.add_query_parser::<UnitCalcParser>();
+        mgr.add_query_parser::<NumeralParser>();
+        mgr.add_query_parser::<TimeZoneParser>();
         mgr.add_query_parser::<AppParser>();
         mgr.add_query_parser::<UnicodeParser>();
         mgr.start().await.unwrap();
@@ -0,0 +1,411 @@
+use async_trait::async_trait;
+use regex::Regex;
+use tokio::sync::mpsc;
+
+use crate::{
+    clipboard::copy_or_report,
+    query_manager::{ListEntry, QueryParser},
+    unit_calc_parser::number_format::NumberFormat,
+};
+
+const ROMAN: &[(u64, &str)] = &[
+    (1000, "M"),
+    (900, "CM"),
+    (500, "D"),
+    (400, "CD"),
+    (100, "C"),
+    (90, "XC"),
+    (50, "L"),
+    (40, "XL"),
+    (10, "X"),
+    (9, "IX"),
+    (5, "V"),
+    (4, "IV"),
+    (1, "I"),
+];
+const ENGLISH_ONES: &[&str] = &[
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven",
+    "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen",
+];
+const ENGLISH_TENS: &[&str] = &[
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+const ENGLISH_SCALES: &[(u64, &str)] = &[
+    (1_000_000_000_000_000_000, "quintillion"),
+    (1_000_000_000_000_000, "quadrillion"),
+    (1_000_000_000_000, "trillion"),
+    (1_000_000_000, "billion"),
+    (1_000_000, "million"),
+    (1_000, "thousand"),
+];
+const GERMAN_ONES: &[&str] = &[
+    "null", "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun", "zehn", "elf",
+    "zwölf", "dreizehn", "vierzehn", "fünfzehn", "sechzehn", "siebzehn", "achtzehn", "neunzehn",
+];
+const GERMAN_TENS: &[&str] = &[
+    "", "", "zwanzig", "dreißig", "vierzig", "fünfzig", "sechzig", "siebzig", "achtzig", "neunzig",
+];
+/// singular and plural, the long scale that German uses
+const GERMAN_SCALES: &[(u64, &str, &str)] = &[
+    (1_000_000_000_000_000_000, "Trillion", "Trillionen"),
+    (1_000_000_000_000_000, "Billiarde", "Billiarden"),
+    (1_000_000_000_000, "Billion", "Billionen"),
+    (1_000_000_000, "Milliarde", "Milliarden"),
+    (1_000_000, "Million", "Millionen"),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Language {
+    English,
+    German,
+}
+
+/// roman numerals and numbers written as words, like `MCMXCIV`, `1994 in roman` or `1234 in words`
+#[derive(Clone)]
+pub struct NumeralParser {
+    /// the calculator's group separator, so `1.234` is only a thousand where `.` groups digits
+    group_separator: Option<char>,
+}
+impl Default for NumeralParser {
+    fn default() -> Self {
+        Self {
+            group_separator: NumberFormat::load().group_separator,
+        }
+    }
+}
+
+/// the roman numeral for `1..=3999`
+pub fn to_roman(mut n: u64) -> Option<String> {
+    if !(1..=3999).contains(&n) {
+        return None;
+    }
+    let mut roman = String::new();
+    for (value, numeral) in ROMAN {
+        while n >= *value {
+            roman.push_str(numeral);
+            n -= value;
+        }
+    }
+    Some(roman)
+}
+/// the value of a roman numeral in capitals and its usual form, so `mix` or `IIII` are not read as one
+pub fn parse_roman(s: &str) -> Option<u64> {
+    if s.is_empty() || !s.chars().all(|c| "IVXLCDM".contains(c)) {
+        return None;
+    }
+    let mut rest = s;
+    let mut n = 0;
+    for (value, numeral) in ROMAN {
+        while let Some(r) = rest.strip_prefix(numeral) {
+            n += value;
+            rest = r;
+        }
+    }
+    // `IIII` or `VX` parse as well, but are not how the number is written
+    (rest.is_empty() && to_roman(n).as_deref() == Some(s)).then_some(n)
+}
+/// `1..=999` in English, e.g. `three hundred forty-two`
+fn english_below_thousand(n: u64) -> String {
+    let mut words = Vec::new();
+    if n >= 100 {
+        words.push(format!("{} hundred", ENGLISH_ONES[(n / 100) as usize]));
+    }
+    match n % 100 {
+        0 => {}
+        r if r < 20 => words.push(ENGLISH_ONES[r as usize].to_string()),
+        r if r % 10 == 0 => words.push(ENGLISH_TENS[(r / 10) as usize].to_string()),
+        r => words.push(format!("{}-{}", ENGLISH_TENS[(r / 10) as usize], ENGLISH_ONES[(r % 10) as usize])),
+    }
+    words.join(" ")
+}
+fn to_english(mut n: u64) -> String {
+    if n == 0 {
+        return ENGLISH_ONES[0].to_string();
+    }
+    let mut words = Vec::new();
+    for (scale, name) in ENGLISH_SCALES {
+        if n >= *scale {
+            words.push(format!("{} {name}", english_below_thousand(n / scale)));
+            n %= scale;
+        }
+    }
+    if n > 0 {
+        words.push(english_below_thousand(n));
+    }
+    words.join(" ")
+}
+/// `1..=999` in German, written as one word; `ein` instead of `eins` when something follows
+fn german_below_thousand(n: u64) -> String {
+    let mut word = String::new();
+    if n >= 100 {
+        let hundreds = n / 100;
+        word.push_str(if hundreds == 1 { "ein" } else { GERMAN_ONES[hundreds as usize] });
+        word.push_str("hundert");
+    }
+    match n % 100 {
+        0 => {}
+        r if r < 20 => word.push_str(GERMAN_ONES[r as usize]),
+        r if r % 10 == 0 => word.push_str(GERMAN_TENS[(r / 10) as usize]),
+        // einundzwanzig, the ones come first
+        r => {
+            let ones = r % 10;
+            word.push_str(if ones == 1 { "ein" } else { GERMAN_ONES[ones as usize] });
+            word.push_str("und");
+            word.push_str(GERMAN_TENS[(r / 10) as usize]);
+        }
+    }
+    word
+}
+fn to_german(mut n: u64) -> String {
+    if n == 0 {
+        return GERMAN_ONES[0].to_string();
+    }
+    let mut words = Vec::new();
+    for (scale, singular, plural) in GERMAN_SCALES {
+        if n >= *scale {
+            let count = n / scale;
+            words.push(match count {
+                1 => format!("eine {singular}"),
+                c => format!("{} {plural}", to_german(c)),
+            });
+            n %= scale;
+        }
+    }
+    // everything below a million is a single word
+    let mut word = String::new();
+    if n >= 1000 {
+        let thousands = n / 1000;
+        word.push_str(&if thousands == 1 { "ein".to_string() } else { german_below_thousand(thousands) });
+        word.push_str("tausend");
+        n %= 1000;
+    }
+    if n > 0 {
+        word.push_str(&german_below_thousand(n));
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words.join(" ")
+}
+/// `n` written out in `language`
+pub fn to_words(n: u64, language: Language) -> String {
+    match language {
+        Language::English => to_english(n),
+        Language::German => to_german(n),
+    }
+}
+/// what a number word does: add to the current group, multiply it by a hundred, or close it with a scale
+#[derive(Clone, Copy)]
+enum NumberWord {
+    Value(u64),
+    Hundred,
+    Scale(u64),
+    /// `and` in `one hundred and five`, `und` in `dreiundzwanzig`
+    Filler,
+}
+/// sums up number words as they are read
+fn combine(words: impl IntoIterator<Item = NumberWord>) -> Option<u64> {
+    let (mut total, mut group, mut any) = (0u64, 0u64, false);
+    for word in words {
+        match word {
+            NumberWord::Value(v) => group = group.checked_add(v)?,
+            // `hundred` alone means one hundred
+            NumberWord::Hundred => group = group.max(1).checked_mul(100)?,
+            NumberWord::Scale(s) => {
+                total = total.checked_add(group.max(1).checked_mul(s)?)?;
+                group = 0;
+            }
+            NumberWord::Filler => continue,
+        }
+        any = true;
+    }
+    any.then(|| total.checked_add(group))?
+}
+fn english_word(word: &str) -> Option<NumberWord> {
+    if word == "and" {
+        return Some(NumberWord::Filler);
+    }
+    if word == "hundred" {
+        return Some(NumberWord::Hundred);
+    }
+    if let Some(i) = ENGLISH_ONES.iter().position(|w| *w == word) {
+        return Some(NumberWord::Value(i as u64));
+    }
+    if let Some(i) = ENGLISH_TENS.iter().position(|w| !w.is_empty() && *w == word) {
+        return Some(NumberWord::Value(i as u64 * 10));
+    }
+    ENGLISH_SCALES.iter().find(|(_, name)| *name == word).map(|(scale, _)| NumberWord::Scale(*scale))
+}
+/// `twelve thousand three hundred` or `forty-two`
+fn parse_english(s: &str) -> Option<u64> {
+    let words = s
+        .split(|c: char| c.is_whitespace() || c == '-' || c == ',')
+        .filter(|w| !w.is_empty())
+        .map(english_word)
+        .collect::<Option<Vec<NumberWord>>>()?;
+    combine(words)
+}
+/// the German number words, longer words first so `dreißig` is not read as `drei`
+fn german_lexicon() -> Vec<(String, NumberWord)> {
+    let mut lexicon = vec![
+        ("und".to_string(), NumberWord::Filler),
+        ("ein".to_string(), NumberWord::Value(1)),
+        ("eine".to_string(), NumberWord::Value(1)),
+        ("hundert".to_string(), NumberWord::Hundred),
+        ("tausend".to_string(), NumberWord::Scale(1000)),
+    ];
+    for (i, word) in GERMAN_ONES.iter().enumerate() {
+        lexicon.push((word.to_string(), NumberWord::Value(i as u64)));
+    }
+    for (i, word) in GERMAN_TENS.iter().enumerate().filter(|(_, w)| !w.is_empty()) {
+        lexicon.push((word.to_string(), NumberWord::Value(i as u64 * 10)));
+    }
+    for (scale, singular, plural) in GERMAN_SCALES {
+        lexicon.push((singular.to_lowercase(), NumberWord::Scale(*scale)));
+        lexicon.push((plural.to_lowercase(), NumberWord::Scale(*scale)));
+    }
+    lexicon.sort_by_key(|(word, _)| std::cmp::Reverse(word.chars().count()));
+    lexicon
+}
+/// `zwölftausenddreihundert` or `eine Million zweihunderttausend`, with the words split up
+fn parse_german(s: &str) -> Option<u64> {
+    let lexicon = german_lexicon();
+    let mut rest = s.replace("ss", "ß").split_whitespace().collect::<String>();
+    let mut words = Vec::new();
+    while !rest.is_empty() {
+        let (word, number_word) = lexicon.iter().find(|(word, _)| rest.starts_with(word.as_str()))?;
+        words.push(*number_word);
+        rest = rest[word.len()..].to_string();
+    }
+    combine(words)
+}
+/// the value of number words in English or German, and which language they were in
+pub fn parse_words(s: &str) -> Option<(u64, Language)> {
+    let s = s.trim().to_lowercase();
+    if let Some(n) = parse_english(&s) {
+        return Some((n, Language::English));
+    }
+    parse_german(&s).map(|n| (n, Language::German))
+}
+/// `1234`, or `1,234` and `1,234,567` grouped with `separator`; anything else, like the decimal
+/// `1.5`, has no roman numeral or words
+fn parse_grouped(s: &str, separator: Option<char>) -> Option<u64> {
+    if let Some(separator) = separator
+        && s.contains(separator)
+    {
+        let mut groups = s.split(separator);
+        let first = groups.next()?;
+        if first.is_empty() || first.len() > 3 || groups.any(|g| g.len() != 3) {
+            return None;
+        }
+    }
+    let digits = s.chars().filter(|c| Some(*c) != separator).collect::<String>();
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+/// `1994 in roman`, `1234 in words` or `1234 in worten`
+fn parse_conversion(query: &str, separator: Option<char>) -> Option<(String, &'static str)> {
+    let re = Regex::new(
+        r"(?i)^\s*(\d[\d,._' ]*?)\s+(?:in|to|as)\s+(roman(?:\s+numerals?)?|(?:english\s+)?words|worten|german(?:\s+words)?|deutsch)\s*$",
+    )
+    .unwrap();
+    let captures = re.captures(query)?;
+    let n = parse_grouped(&captures[1], separator)?;
+    let target = captures[2].to_lowercase();
+    if target.starts_with("roman") {
+        return to_roman(n).map(|r| (r, "roman numeral"));
+    }
+    if target == "worten" || target.starts_with("german") || target == "deutsch" {
+        return Some((to_words(n, Language::German), "in Worten"));
+    }
+    Some((to_words(n, Language::English), "in words"))
+}
+#[async_trait]
+impl QueryParser for NumeralParser {
+    async fn parse(&self, query: String, resopnse: mpsc::Sender<ListEntry>) {
+        let trimmed = query.trim();
+        let (text, detail) = if let Some(conversion) = parse_conversion(trimmed, self.group_separator) {
+            conversion
+        // a single letter like `C` is more likely a unit
+        } else if let Some(n) = parse_roman(trimmed).filter(|_| trimmed.len() > 1) {
+            (n.to_string(), "roman numeral")
+        } else if let Some((n, language)) = parse_words(trimmed) {
+            // a single digit word like `one` is not worth a result
+            if !trimmed.contains(|c: char| c.is_whitespace() || c == '-') && n < 10 {
+                return;
+            }
+            (n.to_string(), if language == Language::German { "Zahlwort" } else { "number words" })
+        } else {
+            return;
+        };
+        let text2 = text.clone();
+        resopnse
+            .send(ListEntry {
+                layout_fn: Box::new(move |ui| {
+                    ui.label(&text);
+                    ui.weak(detail);
+                }),
+                execute: Some(Box::new(move || {
+                    copy_or_report(&text2);
+                })),
+                priority: query.len() as f32,
+                fill_query: None,
+            })
+            .await
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roman_round_trip() {
+        for n in 1..=3999 {
+            assert_eq!(parse_roman(&to_roman(n).unwrap()), Some(n), "{n}");
+        }
+        assert_eq!(to_roman(0), None);
+        assert_eq!(to_roman(4000), None);
+    }
+
+    #[test]
+    fn words_round_trip() {
+        let large = [
+            1_000_000,
+            1_000_001,
+            1_234_567,
+            21_000_000,
+            1_000_000_000,
+            2_000_000_001,
+            999_999_999_999,
+            1_000_000_000_000_000_000,
+        ];
+        for n in (0..=2100).chain(large) {
+            for language in [Language::English, Language::German] {
+                let words = to_words(n, language);
+                assert_eq!(parse_words(&words).map(|(n, _)| n), Some(n), "{words}");
+            }
+        }
+        assert_eq!(to_words(1994, Language::English), "one thousand nine hundred ninety-four");
+        assert_eq!(to_words(1994, Language::German), "eintausendneunhundertvierundneunzig");
+    }
+
+    #[test]
+    fn conversions() {
+        let comma = Some(',');
+        assert_eq!(parse_conversion("1,234 in words", comma).map(|(w, _)| w), Some(to_words(1234, Language::English)));
+        assert_eq!(parse_conversion("1234 in roman", comma).map(|(r, _)| r), Some("MCCXXXIV".to_string()));
+        assert_eq!(parse_conversion("1.234 in roman", comma), None);
+        assert_eq!(parse_conversion("1.5 in words", comma), None);
+        assert_eq!(parse_conversion("1,2345 in words", comma), None);
+        assert_eq!(parse_conversion("1,234.5 in words", comma), None);
+        // where `.` groups digits, as in German
+        assert_eq!(parse_conversion("1.234 in roman", Some('.')).map(|(r, _)| r), Some("MCCXXXIV".to_string()));
+        assert_eq!(parse_conversion("1,234 in roman", Some('.')), None);
+        assert_eq!(parse_conversion("1 234 in worten", Some(' ')).map(|(w, _)| w), Some(to_words(1234, Language::German)));
+        assert_eq!(parse_conversion("1 234 in worten", None), None);
+    }
+}
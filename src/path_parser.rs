@@ -6,12 +6,8 @@ use tokio::sync::mpsc;
 use crate::query_manager::{ListEntry, QueryParser};
 
 #[derive(Clone)]
+#[derive(Default)]
 pub struct PathParser {}
-impl Default for PathParser {
-    fn default() -> Self {
-        Self {}
-    }
-}
 #[async_trait]
 impl QueryParser for PathParser {
     async fn parse(&self, query: String, resopnse: mpsc::Sender<ListEntry>) {
@@ -30,6 +26,7 @@ impl QueryParser for PathParser {
                         std::process::exit(0);
                     })),
                     priority: 10.0,
+                    fill_query: None,
                 })
                 .await
                 .unwrap();
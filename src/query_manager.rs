@@ -26,6 +26,14 @@ pub struct ListEntry {
     pub layout_fn: LayoutFn,
     pub execute: Option<ExecuteFn>,
     pub priority:f32,
+    /// selecting the entry puts this into the search box instead of executing it
+    pub fill_query: Option<String>,
+}
+impl ListEntry {
+    /// whether the entry can be selected and activated with enter
+    pub fn selectable(&self) -> bool {
+        self.execute.is_some() || self.fill_query.is_some()
+    }
 }
 
 pub enum ChangeInstruction {
@@ -72,7 +80,7 @@ impl QueryManager {
                 for p in &self.parsers{
                     parsers.push(p.clone_box());
                 }
-                if receiver.len()>0{
+                if !receiver.is_empty(){
                     continue;
                 }
                 for h in handles.iter(){
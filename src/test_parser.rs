@@ -4,13 +4,9 @@ use tokio::sync::mpsc;
 use crate::query_manager::{ListEntry, QueryParser};
 
 #[derive(Clone)]
+#[derive(Default)]
 pub struct TestParser{
 
-}
-impl Default for TestParser{
-    fn default() -> Self {
-        Self {  }
-    }
 }
 #[async_trait]
 impl QueryParser for TestParser{
@@ -20,7 +16,7 @@ impl QueryParser for TestParser{
                 ui.label(format!("{}",c));
             }), execute: Some(Box::new(move ||{
                 println!("{}",c);
-            })), priority: 0.0 }).await.unwrap();
+            })), priority: 0.0, fill_query: None }).await.unwrap();
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,216 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use jiff::{
+    Timestamp, Zoned,
+    civil::Time,
+    tz::{self, Offset, TimeZone},
+};
+use regex::Regex;
+use tokio::sync::mpsc;
+
+use crate::{
+    clipboard::copy_or_report,
+    query_manager::{ListEntry, QueryParser},
+    unit_calc_parser::datetime::parse_time_zone,
+};
+
+/// well known cities that have no zone of their own in the tz database
+const CITY_ALIASES: &[(&str, &str)] = &[
+    ("san francisco", "America/Los_Angeles"),
+    ("seattle", "America/Los_Angeles"),
+    ("washington", "America/New_York"),
+    ("boston", "America/New_York"),
+    ("miami", "America/New_York"),
+    ("atlanta", "America/New_York"),
+    ("dallas", "America/Chicago"),
+    ("houston", "America/Chicago"),
+    ("austin", "America/Chicago"),
+    ("munich", "Europe/Berlin"),
+    ("frankfurt", "Europe/Berlin"),
+    ("hamburg", "Europe/Berlin"),
+    ("cologne", "Europe/Berlin"),
+    ("geneva", "Europe/Zurich"),
+    ("barcelona", "Europe/Madrid"),
+    ("milan", "Europe/Rome"),
+    ("beijing", "Asia/Shanghai"),
+    ("shenzhen", "Asia/Shanghai"),
+    ("delhi", "Asia/Kolkata"),
+    ("new delhi", "Asia/Kolkata"),
+    ("mumbai", "Asia/Kolkata"),
+    ("bangalore", "Asia/Kolkata"),
+    ("osaka", "Asia/Tokyo"),
+    ("kyoto", "Asia/Tokyo"),
+];
+
+#[derive(Clone)]
+pub struct TimeZoneParser {
+    /// lowercase city name to tz database name, e.g. `new york` to `America/New_York`
+    cities: Arc<HashMap<String, String>>,
+    /// abbreviations like `PST` with the offset they stand for
+    abbreviations: Arc<HashMap<String, Offset>>,
+}
+impl Default for TimeZoneParser {
+    fn default() -> Self {
+        let mut cities = HashMap::new();
+        // abbreviation -> offset -> number of zones using it, to settle ambiguous ones like CST
+        let mut abbreviation_counts: HashMap<String, HashMap<i32, usize>> = HashMap::new();
+        let year = Zoned::now().year();
+        let seasons = [1, 7]
+            .iter()
+            .filter_map(|month| jiff::civil::date(year, *month, 15).to_zoned(TimeZone::UTC).ok())
+            .map(|z| z.timestamp())
+            .collect::<Vec<Timestamp>>();
+        for name in tz::db().available() {
+            let name = name.as_str().to_string();
+            if let Some((_, city)) = name.rsplit_once('/') {
+                cities
+                    .entry(city.replace('_', " ").to_lowercase())
+                    .or_insert(name.clone());
+            }
+            let Ok(zone) = TimeZone::get(&name) else {
+                continue;
+            };
+            // summer and winter, to get both PST and PDT
+            for t in &seasons {
+                let info = zone.to_offset_info(*t);
+                let abbreviation = info.abbreviation();
+                if abbreviation.chars().all(|c| c.is_ascii_uppercase()) {
+                    *abbreviation_counts
+                        .entry(abbreviation.to_string())
+                        .or_default()
+                        .entry(info.offset().seconds())
+                        .or_default() += 1;
+                }
+            }
+        }
+        for (city, zone) in CITY_ALIASES {
+            cities.insert(city.to_string(), zone.to_string());
+        }
+        let abbreviations = abbreviation_counts
+            .into_iter()
+            .filter_map(|(abbreviation, offsets)| {
+                let (seconds, _) = offsets.into_iter().max_by_key(|(_, count)| *count)?;
+                Some((abbreviation, Offset::from_seconds(seconds).ok()?))
+            })
+            .collect();
+        Self {
+            cities: Arc::new(cities),
+            abbreviations: Arc::new(abbreviations),
+        }
+    }
+}
+impl TimeZoneParser {
+    /// a city, an abbreviation, an offset like `UTC+9` or a tz database name
+    fn resolve(&self, place: &str) -> Option<TimeZone> {
+        let place = place.trim();
+        match place.to_lowercase().as_str() {
+            "" | "here" | "local" => return Some(TimeZone::system()),
+            lower => {
+                if let Some(zone) = self.cities.get(lower) {
+                    return TimeZone::get(zone).ok();
+                }
+            }
+        }
+        if let Some(zone) = parse_time_zone(place) {
+            return Some(zone);
+        }
+        self.abbreviations
+            .get(&place.to_uppercase())
+            .map(|offset| TimeZone::fixed(*offset))
+    }
+}
+/// `15:00`, `9am`, `9:30 pm`, `noon` or `midnight`; `None` means now
+fn parse_time(s: &str) -> Option<Option<Time>> {
+    match s.to_lowercase().as_str() {
+        "now" => return Some(None),
+        "noon" => return Some(Some(Time::constant(12, 0, 0, 0))),
+        "midnight" => return Some(Some(Time::midnight())),
+        _ => {}
+    }
+    let re = Regex::new(r"(?i)^(\d{1,2})(?::(\d{2}))?\s*(am|pm)?$").unwrap();
+    let captures = re.captures(s.trim())?;
+    let mut hour: i8 = captures[1].parse().ok()?;
+    let minute: i8 = captures.get(2).map_or(Ok(0), |m| m.as_str().parse()).ok()?;
+    match captures.get(3).map(|m| m.as_str().to_lowercase()).as_deref() {
+        Some("am") if hour == 12 => hour = 0,
+        Some("pm") if hour < 12 => hour += 12,
+        _ => {}
+    }
+    Time::new(hour, minute, 0, 0).ok().map(Some)
+}
+fn describe_offset(z: &Zoned) -> String {
+    let info = z.time_zone().to_offset_info(z.timestamp());
+    let abbreviation = info.abbreviation();
+    if abbreviation.starts_with(['+', '-']) {
+        format!("UTC{}", z.offset())
+    } else {
+        format!("UTC{} {}", z.offset(), abbreviation)
+    }
+}
+fn describe_shift(from: &Zoned, to: &Zoned) -> &'static str {
+    match to.date().cmp(&from.date()) {
+        std::cmp::Ordering::Less => "previous day",
+        std::cmp::Ordering::Equal => "same day",
+        std::cmp::Ordering::Greater => "next day",
+    }
+}
+#[async_trait]
+impl QueryParser for TimeZoneParser {
+    async fn parse(&self, query: String, resopnse: mpsc::Sender<ListEntry>) {
+        let time_in = Regex::new(r"(?i)^\s*time\s+in\s+(.+?)\s*$").unwrap();
+        let convert = Regex::new(
+            r"(?i)^\s*(now|noon|midnight|\d{1,2}:\d{2}\s*(?:am|pm)?|\d{1,2}\s*(?:am|pm))\s*(.*?)\s+(?:in|to|as|=>|->)\s+(.+?)\s*$",
+        )
+        .unwrap();
+        let (from, to, place) = if let Some(captures) = time_in.captures(&query) {
+            let Some(zone) = self.resolve(&captures[1]) else {
+                return;
+            };
+            let now = Zoned::now();
+            (now.clone(), now.with_time_zone(zone), captures[1].to_string())
+        } else if let Some(captures) = convert.captures(&query) {
+            let (Some(time), Some(from_zone), Some(to_zone)) = (
+                parse_time(&captures[1]),
+                self.resolve(&captures[2]),
+                self.resolve(&captures[3]),
+            ) else {
+                return;
+            };
+            let now = Zoned::now().with_time_zone(from_zone.clone());
+            let from = match time {
+                Some(t) => match now.date().to_datetime(t).to_zoned(from_zone) {
+                    Ok(z) => z,
+                    Err(_) => return,
+                },
+                None => now,
+            };
+            let to = from.with_time_zone(to_zone);
+            (from, to, captures[3].to_string())
+        } else {
+            return;
+        };
+        let text = format!("{} in {place}", to.strftime("%H:%M, %a %d %b"));
+        let detail = format!("{}, {}", describe_shift(&from, &to), describe_offset(&to));
+        let human = format!("{} {place} ({})", to.strftime("%H:%M"), to.strftime("%a, %b %d"));
+        let iso = to.strftime("%Y-%m-%dT%H:%M:%S%:z").to_string();
+        let human2 = human.clone();
+        resopnse
+            .send(ListEntry {
+                layout_fn: Box::new(move |ui| {
+                    ui.label(&text);
+                    ui.weak(&detail);
+                    if ui.small_button("copy ISO").on_hover_text(&iso).clicked() {
+                        copy_or_report(&iso);
+                    }
+                }),
+                execute: Some(Box::new(move || {
+                    copy_or_report(&human2);
+                })),
+                priority: query.len() as f32,
+                fill_query: None,
+            })
+            .await
+            .unwrap();
+    }
+}
@@ -7,6 +7,7 @@ use image::ImageFormat;
 use serde::Deserialize;
 use tokio::sync::{RwLock, mpsc};
 
+use crate::clipboard::{copy_or_report, last_error};
 use crate::query_manager::{ListEntry, QueryParser};
 
 #[derive(Clone, Deserialize)]
@@ -24,11 +25,12 @@ pub struct UnicodeCharRaw {
     pub name: String,
     pub key: String,
 }
+type Picture = Arc<std::sync::RwLock<(ColorImage, Option<TextureHandle>)>>;
 #[derive(Clone)]
 pub struct UnicodeChar {
     pub name: String,
     pub key: String,
-    pub picture: Option<Arc<std::sync::RwLock<(ColorImage, Option<TextureHandle>)>>>,
+    pub picture: Option<Picture>,
 }
 fn decode_base64_image(data_uri: &str) -> Option<ColorImage> {
     let base64_data = data_uri.split(',').nth(1)?; // strip "data:image/png;base64,"
@@ -88,7 +90,7 @@ impl Default for UnicodeParser {
 impl QueryParser for UnicodeParser {
     async fn parse(&self, query: String, resopnse: mpsc::Sender<ListEntry>) {
         let mut characters = self.unicode.read().await;
-        while characters.len() == 0 {
+        while characters.is_empty() {
             drop(characters);
             tokio::time::sleep(std::time::Duration::from_millis(100)).await;
             characters = self.unicode.read().await;
@@ -138,11 +140,14 @@ impl QueryParser for UnicodeParser {
                         ui.label(format!("{} {}", &s2.key, &s2.name));
                     }),
                     execute: Some(Box::new(move || {
-                        let mut clipboard = arboard::Clipboard::new().unwrap();
-                        clipboard.set_text(s3.key.clone()).unwrap();
-                        std::process::exit(0);
+                        copy_or_report(&s3.key);
+                        // stay open so the window can show why the copy failed
+                        if last_error().is_none() {
+                            std::process::exit(0);
+                        }
                     })),
                     priority,
+                    fill_query: None,
                 })
                 .await
                 .unwrap();
@@ -0,0 +1,112 @@
+use jiff::{
+    SignedDuration, Span, Zoned,
+    civil::DateTime,
+    tz::{Offset, TimeZone},
+};
+use num_bigint::BigInt;
+use num_rational::{BigRational, Rational64};
+use num_traits::{ToPrimitive, Zero};
+use regex::Regex;
+
+use crate::unit_calc_parser::unit_number_parser::{MetricBaseUnit, UnitExp, UnitNumber};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+/// the length of `month` in units.json, so `today + 1 month` can step by calendar months
+const SECONDS_PER_MONTH: i64 = 2_629_746;
+
+/// `now`, `today` and ISO dates like `2026-12-24` or `2026-12-24T18:30`
+pub fn parse_datetime_literal(s: &str) -> Option<Zoned> {
+    match s {
+        "now" => return Some(Zoned::now()),
+        "today" => return Zoned::now().start_of_day().ok(),
+        _ => {}
+    }
+    if s.len() < 10 || !s.starts_with(|c: char| c.is_ascii_digit()) || !s.contains('-') {
+        return None;
+    }
+    s.parse::<DateTime>().ok()?.to_zoned(TimeZone::system()).ok()
+}
+/// `UTC+9`, `GMT-3:30`, `local` or a tz database name like `Asia/Tokyo` or `CET`
+pub fn parse_time_zone(s: &str) -> Option<TimeZone> {
+    let re = Regex::new(r"^(?:UTC|GMT)\s*([+-])(\d{1,2})(?::?(\d{2}))?$").unwrap();
+    if let Some(captures) = re.captures(s) {
+        let hours: i32 = captures[2].parse().ok()?;
+        let minutes: i32 = captures.get(3).map_or(Ok(0), |m| m.as_str().parse()).ok()?;
+        let sign = if &captures[1] == "-" { -1 } else { 1 };
+        return Some(TimeZone::fixed(Offset::from_seconds(sign * (hours * 3600 + minutes * 60)).ok()?));
+    }
+    match s {
+        "UTC" | "GMT" | "Z" => Some(TimeZone::UTC),
+        "local" => Some(TimeZone::system()),
+        s => TimeZone::get(s).ok(),
+    }
+}
+fn seconds(num: BigRational) -> UnitNumber {
+    UnitNumber {
+        num: num.to_f64().unwrap_or(f64::NAN),
+        units: vec![UnitExp {
+            exp: Rational64::from_integer(1),
+            unit: MetricBaseUnit::Second,
+        }],
+        exact: Some(num),
+    }
+}
+/// moves a date by a duration, by whole days or calendar months where the duration allows it
+pub fn add_duration(date: &Zoned, duration: &UnitNumber, negate: bool) -> Result<Zoned, String> {
+    if !duration.addable(seconds(BigRational::zero())) {
+        return Err("only durations can be added to dates!".to_string());
+    }
+    let sign = if negate { -1 } else { 1 };
+    let whole_seconds = match &duration.exact {
+        Some(e) if e.is_integer() => e.to_integer().to_i64(),
+        _ => None,
+    };
+    let result = match whole_seconds {
+        Some(s) if s % SECONDS_PER_DAY == 0 => Span::new()
+            .try_days(sign * s / SECONDS_PER_DAY)
+            .and_then(|span| date.checked_add(span)),
+        Some(s) if s % SECONDS_PER_MONTH == 0 => Span::new()
+            .try_months(sign * s / SECONDS_PER_MONTH)
+            .and_then(|span| date.checked_add(span)),
+        _ => SignedDuration::try_from_secs_f64(sign as f64 * duration.num)
+            .and_then(|d| date.checked_add(d)),
+    };
+    result.map_err(|e| e.to_string())
+}
+/// the time between two dates, in seconds
+pub fn difference(a: &Zoned, b: &Zoned) -> UnitNumber {
+    let d = a.timestamp().duration_since(b.timestamp());
+    seconds(
+        BigRational::from_integer(BigInt::from(d.as_secs()))
+            + BigRational::new(BigInt::from(d.subsec_nanos()), BigInt::from(1_000_000_000)),
+    )
+}
+pub fn from_unix(n: &UnitNumber) -> Result<Zoned, String> {
+    if !n.units.is_empty() && !n.addable(seconds(BigRational::zero())) {
+        return Err("unix timestamps are seconds!".to_string());
+    }
+    let timestamp =
+        jiff::Timestamp::from_duration(SignedDuration::try_from_secs_f64(n.num).map_err(|e| e.to_string())?)
+            .map_err(|e| e.to_string())?;
+    Ok(timestamp.to_zoned(TimeZone::system()))
+}
+pub fn to_unix(date: &Zoned) -> UnitNumber {
+    let mut n = difference(date, &jiff::Timestamp::UNIX_EPOCH.to_zoned(TimeZone::UTC));
+    n.units.clear();
+    n
+}
+/// dates at midnight are shown without a time
+pub fn format_datetime(date: &Zoned) -> String {
+    if date.time() == jiff::civil::Time::midnight() {
+        date.strftime("%A, %Y-%m-%d").to_string()
+    } else {
+        date.strftime("%Y-%m-%d %H:%M:%S %Z").to_string()
+    }
+}
+/// ISO 8601 and the unix timestamp, as extra copy actions
+pub fn datetime_alternatives(date: &Zoned) -> Vec<String> {
+    vec![
+        date.strftime("%Y-%m-%dT%H:%M:%S%:z").to_string(),
+        date.timestamp().as_second().to_string(),
+    ]
+}
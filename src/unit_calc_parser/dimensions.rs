@@ -0,0 +1,160 @@
+use num_bigint::BigInt;
+use num_rational::{BigRational, Rational64};
+
+use crate::unit_calc_parser::{
+    environment::Environment,
+    lexer::{Unit, lex_all},
+    parser::{UnitConversion, parse_unit_conversion},
+    simplify::NEVER_CHOSEN,
+    unit_number_parser::{MetricBaseUnit, UnitExp, UnitNumber, with_exponent},
+    unit_registry::UnitRegistry,
+};
+
+/// physical quantities with a unit they are measured in
+const QUANTITIES: &[(&str, &str)] = &[
+    ("length", "m"),
+    ("mass", "kg"),
+    ("time", "s"),
+    ("electric current", "A"),
+    ("temperature", "K"),
+    ("amount of substance", "mol"),
+    ("luminous intensity", "cd"),
+    ("information", "B"),
+    ("angle", "rad"),
+    ("solid angle", "sr"),
+    ("area", "m^2"),
+    ("volume", "m^3"),
+    ("velocity", "m/s"),
+    ("acceleration", "m/s^2"),
+    ("jerk", "m/s^3"),
+    ("frequency", "Hz"),
+    ("angular velocity", "rad/s"),
+    ("force", "N"),
+    ("weight", "N"),
+    ("momentum", "kg m/s"),
+    ("energy", "J"),
+    ("work", "J"),
+    ("heat", "J"),
+    ("torque", "N m"),
+    ("power", "W"),
+    ("radiant flux", "W"),
+    ("action", "J s"),
+    ("angular momentum", "J s"),
+    ("pressure", "Pa"),
+    ("stress", "Pa"),
+    ("energy density", "J/m^3"),
+    ("density", "kg/m^3"),
+    ("specific energy", "J/kg"),
+    ("absorbed dose", "Gy"),
+    ("surface tension", "N/m"),
+    ("dynamic viscosity", "Pa s"),
+    ("kinematic viscosity", "m^2/s"),
+    ("volumetric flow rate", "m^3/s"),
+    ("mass flow rate", "kg/s"),
+    ("irradiance", "W/m^2"),
+    ("heat capacity", "J/K"),
+    ("entropy", "J/K"),
+    ("specific heat capacity", "J/(kg K)"),
+    ("thermal conductivity", "W/(m K)"),
+    ("molar mass", "kg/mol"),
+    ("concentration", "mol/m^3"),
+    ("electric charge", "C"),
+    ("voltage", "V"),
+    ("electric field", "V/m"),
+    ("resistance", "Ω"),
+    ("conductance", "S"),
+    ("capacitance", "F"),
+    ("inductance", "H"),
+    ("magnetic flux", "Wb"),
+    ("magnetic flux density", "T"),
+    ("luminous flux", "lm"),
+    ("illuminance", "lx"),
+    ("radioactivity", "Bq"),
+    ("data rate", "B/s"),
+];
+
+/// the value of a unit expression like `kg m^2 s^-3`
+pub fn evaluate_units(expression: &str, units: &UnitRegistry, env: &Environment) -> Result<UnitNumber, String> {
+    let (tokens, _) = lex_all(expression.to_string(), units, env)
+        .into_iter()
+        .next()
+        .ok_or("lexing failed!".to_string())?;
+    match parse_unit_conversion(tokens)? {
+        UnitConversion::Calculation(c) => c.execute(),
+        _ => Err("expected units without a conversion".to_string()),
+    }
+}
+/// kilograms first, as in kg·m²·s⁻³
+fn base_order(b: &MetricBaseUnit) -> (usize, String) {
+    let order = [
+        MetricBaseUnit::Gramm,
+        MetricBaseUnit::Meter,
+        MetricBaseUnit::Second,
+        MetricBaseUnit::Ampere,
+        MetricBaseUnit::Kelvin,
+        MetricBaseUnit::Mole,
+        MetricBaseUnit::Candela,
+        MetricBaseUnit::Byte,
+    ];
+    (order.iter().position(|o| o == b).unwrap_or(order.len()), b.to_string())
+}
+/// the dimension symbol, L for length or Θ for temperature
+fn dimension_symbol(b: &MetricBaseUnit) -> String {
+    match b {
+        MetricBaseUnit::Meter => "L",
+        MetricBaseUnit::Gramm => "M",
+        MetricBaseUnit::Second => "T",
+        MetricBaseUnit::Ampere => "I",
+        MetricBaseUnit::Kelvin => "Θ",
+        MetricBaseUnit::Mole => "N",
+        MetricBaseUnit::Candela => "J",
+        MetricBaseUnit::Byte => "B",
+        MetricBaseUnit::Custom(s) => s,
+    }
+    .to_string()
+}
+/// the base units and dimension symbols of `un`, like `kg·m²·s⁻³` and `M·L²·T⁻³`
+pub fn signature(un: &UnitNumber) -> (String, String) {
+    let mut exponents = un.cleaned().units;
+    exponents.sort_by_key(|e| (e.exp < Rational64::from_integer(0), base_order(&e.unit)));
+    let join = |name: &dyn Fn(&MetricBaseUnit) -> String| {
+        exponents.iter().map(|e| with_exponent(name(&e.unit), e.exp)).collect::<Vec<String>>().join("·")
+    };
+    let base_unit = |b: &MetricBaseUnit| match b {
+        MetricBaseUnit::Gramm => "kg".to_string(),
+        MetricBaseUnit::Kelvin => "K".to_string(),
+        b => b.to_string(),
+    };
+    (join(&base_unit), join(&dimension_symbol))
+}
+/// the number that goes with the units of `signature`, which counts kilograms instead of grams
+pub fn in_kilograms(un: &UnitNumber) -> UnitNumber {
+    let kilogram = UnitNumber {
+        num: 1000.0,
+        units: vec![UnitExp { exp: Rational64::from_integer(1), unit: MetricBaseUnit::Gramm }],
+        exact: Some(BigRational::from_integer(BigInt::from(1000))),
+    };
+    let grams = un.cleaned().units.iter().find(|e| e.unit == MetricBaseUnit::Gramm).map(|e| e.exp);
+    match grams {
+        Some(exp) => un.clone() / kilogram.pow_rational(exp),
+        None => un.clone(),
+    }
+}
+/// the unprefixed units with the dimension of `un`, like watts and horsepower for kg·m²·s⁻³
+pub fn matching_units<'a>(un: &UnitNumber, units: &'a UnitRegistry) -> Vec<&'a Unit> {
+    let mut matching = units
+        .iter()
+        .filter(|u| u.name == u.base_name && u.priority > NEVER_CHOSEN && un.addable(u.si.cleaned()))
+        .collect::<Vec<&Unit>>();
+    matching.sort_by(|a, b| b.priority.total_cmp(&a.priority));
+    matching
+}
+/// the quantities measured in the dimension of `un`, like power and radiant flux
+pub fn matching_quantities(un: &UnitNumber, units: &UnitRegistry) -> Vec<&'static str> {
+    let env = Environment::default();
+    QUANTITIES
+        .iter()
+        .filter(|(_, unit)| evaluate_units(unit, units, &env).is_ok_and(|q| un.addable(q)))
+        .map(|(name, _)| *name)
+        .collect()
+}
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::unit_calc_parser::unit_number_parser::UnitNumber;
+
+const VARIABLES_FILE: &str = "calc_variables.json";
+
+/// variables that live across queries; `ans` only lasts for the session, the rest is saved to disk
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Environment {
+    pub variables: HashMap<String, UnitNumber>,
+    #[serde(skip)]
+    pub ans: Option<UnitNumber>,
+}
+impl Environment {
+    pub fn load() -> Self {
+        match std::fs::read_to_string(VARIABLES_FILE) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                eprintln!("could not parse {VARIABLES_FILE}: {e}");
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+    pub fn save(&self) -> Result<(), String> {
+        let s = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(VARIABLES_FILE, s).map_err(|e| e.to_string())
+    }
+    pub fn get(&self, name: &str) -> Option<&UnitNumber> {
+        if name == "ans" {
+            return self.ans.as_ref();
+        }
+        self.variables.get(name)
+    }
+    /// stores a variable and writes all variables to disk
+    pub fn set(&mut self, name: String, value: UnitNumber) {
+        self.variables.insert(name, value);
+        if let Err(e) = self.save() {
+            eprintln!("could not save {VARIABLES_FILE}: {e}");
+        }
+    }
+    /// the first of `r1`, `r2`, ... that is not taken yet
+    pub fn next_free_name(&self) -> String {
+        (1..)
+            .map(|i| format!("r{i}"))
+            .find(|n| !self.variables.contains_key(n))
+            .unwrap()
+    }
+}
@@ -0,0 +1,76 @@
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+
+/// exact values with more bits than this are dropped to keep big powers from eating all memory
+const MAX_BITS: u64 = 100_000;
+
+/// parses `12`, `12.5`, `.5` or `1.5e-3` without going through a float
+pub fn parse_decimal(s: &str) -> Option<BigRational> {
+    if let Some((mantissa, exponent)) = s.split_once(['e', 'E']) {
+        let exponent = exponent.parse::<i32>().ok()?;
+        if exponent.unsigned_abs() > 1000 {
+            return None;
+        }
+        let scale = BigRational::from_integer(BigInt::from(10).pow(exponent.unsigned_abs()));
+        let mantissa = parse_decimal(mantissa)?;
+        return Some(if exponent < 0 {
+            mantissa / scale
+        } else {
+            mantissa * scale
+        });
+    }
+    let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+    if !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let digits = format!("{int}{frac}");
+    let numer = if digits.is_empty() {
+        BigInt::zero()
+    } else {
+        digits.parse::<BigInt>().ok()?
+    };
+    Some(BigRational::new(numer, BigInt::from(10).pow(frac.len() as u32)))
+}
+/// drops values that got too big to be useful
+pub fn limit(r: BigRational) -> Option<BigRational> {
+    if r.numer().bits() + r.denom().bits() > MAX_BITS {
+        None
+    } else {
+        Some(r)
+    }
+}
+/// the full decimal expansion, if it terminates
+pub fn to_terminating_decimal(r: &BigRational) -> Option<String> {
+    let mut denom = r.denom().clone();
+    let mut twos = 0;
+    let mut fives = 0;
+    while (&denom % 2u32).is_zero() {
+        denom /= 2u32;
+        twos += 1;
+    }
+    while (&denom % 5u32).is_zero() {
+        denom /= 5u32;
+        fives += 1;
+    }
+    if !denom.is_one() {
+        return None;
+    }
+    let places = twos.max(fives);
+    let scaled = (r.numer().abs() * BigInt::from(10).pow(places)) / r.denom();
+    let mut digits = scaled.to_string();
+    if places > 0 {
+        while digits.len() <= places as usize {
+            digits.insert(0, '0');
+        }
+        digits.insert(digits.len() - places as usize, '.');
+    }
+    if r.is_negative() {
+        digits.insert(0, '-');
+    }
+    Some(digits)
+}
+/// a terminating decimal if there is one, a fraction otherwise
+pub fn to_exact_string(r: &BigRational) -> String {
+    to_terminating_decimal(r).unwrap_or_else(|| format!("{}/{}", r.numer(), r.denom()))
+}
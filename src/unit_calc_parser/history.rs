@@ -0,0 +1,64 @@
+use jiff::Zoned;
+use serde::{Deserialize, Serialize};
+
+const HISTORY_FILE: &str = "calc_history.json";
+/// older calculations are forgotten, pinned ones are always kept
+const MAX_ENTRIES: usize = 100;
+
+/// a calculation that was executed
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub expression: String,
+    pub result: String,
+    pub timestamp: String,
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+/// executed calculations, the most recent first, saved to disk
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct History {
+    pub entries: Vec<HistoryEntry>,
+}
+impl History {
+    pub fn load() -> Self {
+        match std::fs::read_to_string(HISTORY_FILE) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                eprintln!("could not parse {HISTORY_FILE}: {e}");
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+    pub fn save(&self) -> Result<(), String> {
+        let s = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(HISTORY_FILE, s).map_err(|e| e.to_string())
+    }
+    fn save_or_report(&self) {
+        if let Err(e) = self.save() {
+            eprintln!("could not save {HISTORY_FILE}: {e}");
+        }
+    }
+    /// records a calculation; repeating an expression moves it to the top and keeps its pin
+    pub fn add(&mut self, expression: String, result: String) {
+        let pinned = match self.entries.iter().position(|e| e.expression == expression) {
+            Some(i) => self.entries.remove(i).pinned,
+            None => false,
+        };
+        let timestamp = Zoned::now().strftime("%Y-%m-%d %H:%M").to_string();
+        self.entries.insert(0, HistoryEntry { expression, result, timestamp, pinned });
+        let mut unpinned = 0;
+        self.entries.retain(|e| {
+            unpinned += !e.pinned as usize;
+            e.pinned || unpinned <= MAX_ENTRIES
+        });
+        self.save_or_report();
+    }
+    /// pins or unpins every entry with this expression
+    pub fn toggle_pin(&mut self, expression: &str) {
+        for entry in self.entries.iter_mut().filter(|e| e.expression == expression) {
+            entry.pinned = !entry.pinned;
+        }
+        self.save_or_report();
+    }
+}
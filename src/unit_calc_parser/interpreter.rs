@@ -1,28 +1,127 @@
-use crate::unit_calc_parser::{lexer::Unit, parser::{UnitCalculation, UnitConversion}, unit_number_parser::UnitNumber};
+use num_rational::Rational64;
+use num_traits::ToPrimitive;
+
+use std::cmp::Ordering;
+
+use crate::unit_calc_parser::{datetime::{add_duration, difference, from_unix, to_unix}, lexer::{Token, Unit}, parser::{UnitCalculation, UnitConversion}, solver::solve, statistics::{AGGREGATES, aggregate}, unit_number_parser::UnitNumber, value::Value};
+
+/// the value, the unit it was converted to and the target of a conversion into another number format
+type Execution = (Value, Option<Unit>, Option<(UnitNumber,String)>);
 
 impl UnitConversion{
-    pub fn execute(&self) -> Result<(UnitNumber, Option<Unit>, Option<(UnitNumber,String)>),String>{
+    pub fn execute(&self) -> Result<Execution,String>{
         match self{
-            Self::PrimitiveUnitConversion(c, u)=>{
-                Ok((c.execute()?, Some(u.clone()),None))
+            Self::Primitive(c, u)=>{
+                Ok((Value::Number(c.execute()?), Some(u.as_ref().clone()),None))
             },
             Self::Calculation(c)=>{
-                Ok((c.execute()?, None,None))
+                Ok((c.evaluate()?, None,None))
+            },
+            Self::MixedUnits(c, units, clock)=>{
+                let value=c.execute()?;
+                // `1 J in N m` asks for a product of units, not a split like `ft and in`
+                if !*clock && units.iter().any(|u| !value.addable(u.si.clone())){
+                    let product=units.iter().try_fold(UnitNumber::from_decimal("1")?, |acc, u| Ok::<_, String>(acc*u.si.clone()))?;
+                    let symbols=units.iter().map(|u| u.abbreviation.clone()).collect::<Vec<String>>().join(" ");
+                    return Ok((Value::Number(value), None, Some((product, symbols))));
+                }
+                Ok((Value::Number(value), None, None))
+            },
+            Self::Complex(a, b)=>{
+                Ok((Value::Number(a.execute()?),None,Some((b.execute()?,b.symbols()))))
             },
-            Self::ComplexUnitConversion(a, b)=>{
-                Ok((a.execute()?,None,Some((b.execute()?,b.to_string()))))
+            Self::Equation(a, b)=>{
+                let (_, solutions)=solve(a, b)?;
+                Ok((Value::Number(solutions[0].clone()), None, None))
             }
         }
     }
 }
 impl UnitCalculation{
-    pub fn execute(&self)->Result<UnitNumber,String>{
+    pub fn evaluate(&self)->Result<Value,String>{
         match self{
+            Self::Compare(a, c)=>{
+                let mut left=a.evaluate()?;
+                let mut result=true;
+                for (t, b) in c{
+                    let right=b.evaluate()?;
+                    let ordering=match (&left, &right){
+                        (Value::DateTime(l), Value::DateTime(r))=>l.cmp(r),
+                        (l, r)=>l.clone().into_number()?.compare(&r.clone().into_number()?)?,
+                    };
+                    result&=match t{
+                        Token::Lower=>ordering==Ordering::Less,
+                        Token::LowerEq=>ordering!=Ordering::Greater,
+                        Token::Higher=>ordering==Ordering::Greater,
+                        Token::HigherEq=>ordering!=Ordering::Less,
+                        Token::Eq=>ordering==Ordering::Equal,
+                        Token::NEq=>ordering!=Ordering::Equal,
+                        t=>return Err(format!("unknown comparison: {}", t)),
+                    };
+                    left=right;
+                }
+                Ok(Value::Bool(result))
+            },
+            Self::And(a, b)=>{
+                Ok(Value::Bool(a.evaluate()?.into_bool()?&b.evaluate()?.into_bool()?))
+            },
+            Self::Or(a, b)=>{
+                Ok(Value::Bool(a.evaluate()?.into_bool()?|b.evaluate()?.into_bool()?))
+            },
+            Self::Bracket(a)=>{
+                a.evaluate()
+            },
             Self::Plus(a, b)=>{
-                a.execute()?+b.execute()?
+                match (a.evaluate()?, b.evaluate()?){
+                    (Value::DateTime(d), Value::Number(n)) | (Value::Number(n), Value::DateTime(d))=>{
+                        Ok(Value::DateTime(add_duration(&d, &n, false)?))
+                    },
+                    (Value::DateTime(_), Value::DateTime(_))=>Err("dates cannot be added!".to_string()),
+                    (a, b)=>Ok(Value::Number((a.into_number()?+b.into_number()?)?)),
+                }
             },
             Self::Minus(a, b)=>{
-                a.execute()?-b.execute()?
+                match (a.evaluate()?, b.evaluate()?){
+                    (Value::DateTime(d), Value::Number(n))=>Ok(Value::DateTime(add_duration(&d, &n, true)?)),
+                    (Value::DateTime(d1), Value::DateTime(d2))=>Ok(Value::Number(difference(&d1, &d2))),
+                    (a, b)=>Ok(Value::Number((a.into_number()?-b.into_number()?)?)),
+                }
+            },
+            Self::Function(f, a) if f=="unix"=>{
+                match a.evaluate()?{
+                    Value::DateTime(d)=>Ok(Value::Number(to_unix(&d))),
+                    v=>Ok(Value::DateTime(from_unix(&v.into_number()?)?)),
+                }
+            },
+            Self::Function(f, a) if AGGREGATES.contains(&f.as_str())=>{
+                match a.evaluate()?{
+                    Value::List(l)=>Ok(Value::Number(aggregate(f, &l)?)),
+                    _=>Err(format!("{f} expects a list like [1, 2, 3]")),
+                }
+            },
+            Self::List(items)=>{
+                Ok(Value::List(items.iter().map(|i| i.execute()).collect::<Result<_, _>>()?))
+            },
+            Self::DateTime(d)=>Ok(Value::DateTime(d.clone())),
+            _=>Ok(Value::Number(self.execute()?)),
+        }
+    }
+    /// every compound sub-expression with its value, innermost first
+    pub fn steps(&self)->Vec<(String, Result<Value,String>)>{
+        let mut steps=Vec::new();
+        for c in self.children(){
+            steps.extend(c.steps());
+        }
+        // parts with an unknown have no value yet
+        if !self.children().is_empty() && !matches!(self, Self::Bracket(_)) && self.unknowns().is_empty(){
+            steps.push((self.to_string(), self.evaluate()));
+        }
+        steps
+    }
+    pub fn execute(&self)->Result<UnitNumber,String>{
+        match self{
+            Self::Plus(..) | Self::Minus(..) | Self::DateTime(_)=>{
+                self.evaluate()?.into_number()
             },
             Self::Mult(a, b)=>{
                 Ok(a.execute()?*b.execute()?)
@@ -30,20 +129,81 @@ impl UnitCalculation{
             Self::ImplMult(a, b)=>{
                 Ok(a.execute()?*b.execute()?)
             },
-            Self::Div(a, b)=>{
+            Self::Div(a, b) | Self::At(a, b)=>{
                 Ok(a.execute()?/b.execute()?)
             },
             Self::Pow(a, b)=>{
-                Ok(a.execute()?.pow_i64(b.execute()?.to_i64()?))
+                a.execute()?.pow(b.execute()?.to_exponent()?)
             },
             Self::Bracket(a)=>{
                 a.execute()
             },
+            Self::Function(f, a)=>{
+                match f.as_str(){
+                    "√"=>a.execute()?.pow(0.5),
+                    "∛"=>Ok(a.execute()?.pow_rational(Rational64::new(1, 3))),
+                    "unix"=>self.evaluate()?.into_number(),
+                    f if AGGREGATES.contains(&f)=>self.evaluate()?.into_number(),
+                    "~"=>Ok(UnitNumber::from_integer(!a.execute()?.to_integer()?)),
+                    "-"=>Ok(-a.execute()?),
+                    f=>Err(format!("unknown function: {f}")),
+                }
+            },
+            Self::BitAnd(a, b)=>{
+                Ok(UnitNumber::from_integer(a.execute()?.to_integer()?&b.execute()?.to_integer()?))
+            },
+            Self::BitOr(a, b)=>{
+                Ok(UnitNumber::from_integer(a.execute()?.to_integer()?|b.execute()?.to_integer()?))
+            },
+            Self::BitXor(a, b)=>{
+                Ok(UnitNumber::from_integer(a.execute()?.to_integer()?^b.execute()?.to_integer()?))
+            },
+            Self::ShiftLeft(a, b)=>{
+                Ok(UnitNumber::from_integer(a.execute()?.to_integer()?<<shift_amount(b)?))
+            },
+            Self::ShiftRight(a, b)=>{
+                Ok(UnitNumber::from_integer(a.execute()?.to_integer()?>>shift_amount(b)?))
+            },
+            Self::AddPercent(a, p)=>{
+                let a=a.execute()?;
+                a.clone()+(a*p.execute()?)
+            },
+            Self::SubtractPercent(a, p)=>{
+                let a=a.execute()?;
+                a.clone()-(a*p.execute()?)
+            },
+            Self::PercentOf(p, a)=>{
+                Ok(p.execute()?*a.execute()?)
+            },
+            Self::PercentRatio(a, b)=>{
+                Ok(a.execute()?/b.execute()?)
+            },
+            Self::PercentDifference(a, b, less)=>{
+                let (a, b)=(a.execute()?, b.execute()?);
+                let difference=if *less { (b.clone()-a)? } else { (a-b.clone())? };
+                Ok(difference/b)
+            },
+            Self::Unit(u)=>{
+                Ok(u.si.cleaned())
+            },
             Self::Number(n)=>{
                 let mut num = n.clone();
                 num.clean();
                 Ok(num)
+            },
+            Self::Unknown(name)=>{
+                Err(format!("unknown name: {name}"))
+            },
+            Self::Compare(..) | Self::And(..) | Self::Or(..) | Self::List(_)=>{
+                self.evaluate()?.into_number()
             }
         }
     }
+}
+/// shifting by more bits than exact values may have would only produce garbage
+pub fn shift_amount(b: &UnitCalculation) -> Result<usize, String> {
+    match b.execute()?.to_integer()?.to_usize() {
+        Some(n) if n <= 100_000 => Ok(n),
+        _ => Err("shift amounts must be between 0 and 100000!".to_string()),
+    }
 }
\ No newline at end of file
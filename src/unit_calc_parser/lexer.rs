@@ -1,6 +1,18 @@
-use std::{f64, vec};
-
-use crate::unit_calc_parser::unit_number_parser::{MetricBaseUnit, UnitExp, UnitNumber};
+use std::{collections::HashMap, fmt, sync::LazyLock};
+
+use jiff::Zoned;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+
+use crate::unit_calc_parser::{
+    datetime::parse_datetime_literal,
+    environment::Environment,
+    programmer::parse_radix_literal,
+    simplify::NEVER_CHOSEN,
+    unit_definitions::{BUILTIN_UNITS, CONSTANTS, USER_UNITS_FILE, load_unit_definitions},
+    unit_number_parser::UnitNumber,
+    unit_registry::UnitRegistry,
+};
 
 #[derive(Clone, PartialEq)]
 pub enum Token {
@@ -17,16 +29,37 @@ pub enum Token {
     Higher,
     Eq,
     NEq,
+    And,
+    Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
     OpenBracket,
     CloseBracket,
+    OpenList,
+    CloseList,
+    Comma,
     Convert,
+    At,
     Dot,
-    Unit(UnitNumber, Option<Unit>),
+    Colon,
+    Of,
+    IsWhat,
+    MoreThan,
+    LessThan,
+    Equals,
+    /// a name that is neither a unit nor a variable, the unknown of an equation
+    Unknown(String),
+    Function(String),
+    DateTime(Zoned),
+    Unit(UnitNumber, Option<Box<Unit>>),
 }
-impl ToString for Token {
-    fn to_string(&self) -> String {
-        match self {
-            Token::Number(s) => format!("{s}").to_string(),
+impl fmt::Display for Token {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Token::Number(s) => s.clone(),
             Token::Unit(s, _) => s.to_string(),
             Token::StringLiteral(s) => format!("\"{s}\""),
             Token::Plus => "+".to_string(),
@@ -40,22 +73,48 @@ impl ToString for Token {
             Token::Higher => ">".to_string(),
             Token::Eq => "==".to_string(),
             Token::NEq => "!=".to_string(),
+            Token::And => "and".to_string(),
+            Token::Or => "or".to_string(),
+            Token::BitAnd => "&".to_string(),
+            Token::BitOr => "|".to_string(),
+            Token::BitXor => "xor".to_string(),
+            Token::ShiftLeft => "<<".to_string(),
+            Token::ShiftRight => ">>".to_string(),
             Token::OpenBracket => "(".to_string(),
             Token::CloseBracket => ")".to_string(),
+            Token::OpenList => "[".to_string(),
+            Token::CloseList => "]".to_string(),
+            Token::Comma => ",".to_string(),
             Token::Convert => "in".to_string(),
+            Token::At => "at".to_string(),
             Token::Dot => ".".to_string(),
-        }
+            Token::Colon => ":".to_string(),
+            Token::Of => "of".to_string(),
+            Token::IsWhat => "is what".to_string(),
+            Token::MoreThan => "more than".to_string(),
+            Token::LessThan => "less than".to_string(),
+            Token::Equals => "=".to_string(),
+            Token::Unknown(name) => name.clone(),
+            Token::Function(f) => f.clone(),
+            Token::DateTime(d) => d.strftime("%Y-%m-%dT%H:%M:%S").to_string(),
+        };
+        formatter.write_str(&s)
     }
 }
-pub fn lex(input: String, units: &Vec<Unit>) -> Option<Vec<Token>> {
+pub fn lex(input: String, units: &UnitRegistry, env: &Environment) -> Option<Vec<Token>> {
     let chars=input.chars().collect::<Vec<char>>();
     let mut start_id = 0;
     let mut output = Vec::new();
+    let longest = longest_word(units, env);
     while start_id < chars.len() {
-        let mut end_id = chars.len();
+        let mut end_id = if may_be_long(chars[start_id]) {
+            chars.len()
+        } else {
+            chars.len().min(start_id + longest)
+        };
         let mut sucess = false;
         while end_id > start_id {
-            let token = get_token(chars[start_id..end_id].iter().collect::<String>(), units);
+            let token = get_token(chars[start_id..end_id].iter().collect::<String>(), units, env);
             if let Some(t) = token {
                 output.extend(t);
                 start_id = end_id;
@@ -70,8 +129,24 @@ pub fn lex(input: String, units: &Vec<Unit>) -> Option<Vec<Token>> {
     }
     Some(output)
 }
-pub fn get_token(s: String, units: &Vec<Unit>) -> Option<Vec<Token>> {
-    let atomic = vec![
+/// numbers like `6.02e23` or `1E-9`, as results are printed in scientific notation
+fn is_scientific(s: &str) -> bool {
+    let Some((mantissa, exponent)) = s.split_once(['e', 'E']) else {
+        return false;
+    };
+    let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+    mantissa.chars().any(|c| c.is_ascii_digit())
+        && mantissa.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && mantissa.matches('.').count() <= 1
+        && !exponent.is_empty()
+        && exponent.chars().all(|c| c.is_ascii_digit())
+}
+/// for a constant multiplied with a unit without an operator, which is nearly always a prefix
+/// instead, like the `G` of `Gbit`
+const IMPLICIT_CONSTANT_PENALTY: f32 = 3.0;
+/// operators and keywords that always lex to the same tokens
+static ATOMIC: LazyLock<HashMap<&'static str, Vec<Token>>> = LazyLock::new(|| {
+    HashMap::from([
         ("+", vec![Token::Plus]),
         ("-", vec![Token::Minus]),
         ("*", vec![Token::Mult]),
@@ -83,727 +158,228 @@ pub fn get_token(s: String, units: &Vec<Unit>) -> Option<Vec<Token>> {
         (">", vec![Token::Higher]),
         ("==", vec![Token::Eq]),
         ("!=", vec![Token::NEq]),
+        ("and", vec![Token::And]),
+        ("&&", vec![Token::And]),
+        ("or", vec![Token::Or]),
+        ("||", vec![Token::Or]),
+        ("&", vec![Token::BitAnd]),
+        ("|", vec![Token::BitOr]),
+        ("xor", vec![Token::BitXor]),
+        ("<<", vec![Token::ShiftLeft]),
+        (">>", vec![Token::ShiftRight]),
+        ("~", vec![Token::Function("~".to_string())]),
+        ("unix", vec![Token::Function("unix".to_string())]),
         ("(", vec![Token::OpenBracket]),
         (")", vec![Token::CloseBracket]),
+        ("[", vec![Token::OpenList]),
+        ("]", vec![Token::CloseList]),
+        (",", vec![Token::Comma]),
+        ("sum", vec![Token::Function("sum".to_string())]),
+        ("mean", vec![Token::Function("mean".to_string())]),
+        ("average", vec![Token::Function("mean".to_string())]),
+        ("avg", vec![Token::Function("mean".to_string())]),
+        ("median", vec![Token::Function("median".to_string())]),
+        ("minimum", vec![Token::Function("min".to_string())]),
+        ("max", vec![Token::Function("max".to_string())]),
+        ("maximum", vec![Token::Function("max".to_string())]),
+        ("stddev", vec![Token::Function("stddev".to_string())]),
+        ("std", vec![Token::Function("stddev".to_string())]),
         ("as", vec![Token::Convert]),
         ("=>", vec![Token::Convert]),
         ("->", vec![Token::Convert]),
         ("to", vec![Token::Convert]),
+        ("at", vec![Token::At]),
         (".", vec![Token::Dot]),
+        (":", vec![Token::Colon]),
+        ("of", vec![Token::Of]),
+        ("is what", vec![Token::IsWhat]),
+        ("more than", vec![Token::MoreThan]),
+        ("less than", vec![Token::LessThan]),
+        ("=", vec![Token::Equals]),
         ("²", vec![Token::Power,Token::Number("2".to_string())]),
         ("³", vec![Token::Power,Token::Number("3".to_string())]),
-    ];
-    if let Some(t) = atomic.iter().find(|a| a.0 == s) {
-        return Some(t.1.iter().cloned().collect());
+        ("sqrt", vec![Token::Function("√".to_string())]),
+        ("√", vec![Token::Function("√".to_string())]),
+        ("cbrt", vec![Token::Function("∛".to_string())]),
+        ("∛", vec![Token::Function("∛".to_string())]),
+    ])
+});
+/// all tokens `s` could stand for, each with a plausibility adjustment
+pub fn get_tokens(s: String, units: &UnitRegistry, env: &Environment) -> Vec<(Vec<Token>, f32)> {
+    let mut tokens = Vec::new();
+    if let Some(t) = ATOMIC.get(s.as_str()) {
+        tokens.push((t.clone(), 0.0));
     }
-    if s.chars().all(|c| c.is_numeric()) {
-        return Some(vec![Token::Number(s)]);
+    if s.chars().all(|c| c.is_numeric()) || is_scientific(&s) || parse_radix_literal(&s).is_some() {
+        return vec![(vec![Token::Number(s)], 0.0)];
     }
     if s.chars().all(|c| c.is_whitespace()) {
-        return Some(vec![]);
+        return vec![(vec![], 0.0)];
     }
-    if s.starts_with('"') && s.ends_with('"') {
+    if s.len() >= 2 && s.starts_with('"') && s.ends_with('"') {
         let content = s[1..s.len() - 1].to_string();
         if !content.contains('"') {
-            return Some(vec![Token::StringLiteral(content)]);
+            return vec![(vec![Token::StringLiteral(content)], 0.0)];
         }
     }
-    for u in units {
-        for n in u.valid_names.iter() {
-            if *n == s {
-                return Some(vec![Token::Unit(u.si.clone(), Some(u.clone()))]);
-            }
-        }
+    if let Some(d) = parse_datetime_literal(&s) {
+        // `2026-12-24` is also a subtraction of five tokens, which stays close enough to be shown
+        let score = if s.chars().all(|c| c.is_ascii_digit() || c == '-') { -3.5 } else { 0.0 };
+        return vec![(vec![Token::DateTime(d)], score)];
+    }
+    if let Some(v) = env.get(&s) {
+        return vec![(vec![Token::Unit(v.clone(), None)], 0.0)];
+    }
+    // the first unit with a name is its usual meaning, like hours rather than the Planck constant for `h`
+    for (i, u) in units.lookup(&s).enumerate() {
+        tokens.push((
+            vec![Token::Unit(u.si.clone(), Some(Box::new(u.clone())))],
+            unit_plausibility(u) - i as f32,
+        ));
+    }
+    // `in cm` converts far more often than it multiplies by an inch
+    if s == "in" {
+        tokens.push((vec![Token::Convert], 2.0));
+    }
+    // after minutes, so unit definitions like `60 min` keep meaning minutes
+    if s == "min" {
+        tokens.push((vec![Token::Function("min".to_string())], 0.0));
     }
-    None
+    // names that mean nothing else, like `x` in `2x + 5 m = 11 m`, or single letters like `t` for a time
+    let name = s.chars().next().is_some_and(|c| c.is_alphabetic()) && s.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if name && (tokens.is_empty() || s.chars().count() == 1) {
+        tokens.push((vec![Token::Unknown(s)], -4.0));
+    }
+    tokens
 }
-pub fn get_units() -> Vec<Unit> {
-    let mut v = vec![];
-    v.extend(
-        Unit {
-            name: "percent".to_string(),
-            plural: "percents".to_string(),
-            abbreviation: "%".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 1.0e-2,
-                units: vec![],
-            },
-            priority: f32::NEG_INFINITY,
-        }
-        .create()
-        .add_si_prefixes(),
-    );
-    
-    v.extend(
-        Unit {
-            name: "π".to_string(),
-            plural: "π".to_string(),
-            abbreviation: "π".to_string(),
-            valid_names: vec!["pi".to_string(), "PI".to_string(), "Pi".to_string()],
-            si: UnitNumber {
-                num: f64::consts::PI,
-                units: vec![],
-            },
-            priority: 0.0,
-        }
-        .create()
-        .add_si_prefixes(),
-    );
-
-    v.extend(
-        Unit {
-            name: "meter".to_string(),
-            plural: "meters".to_string(),
-            abbreviation: "m".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 1.0,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Meter,
-                    exp: 1,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create()
-        .add_si_prefixes(),
-    );
-
-    v.extend(
-        Unit {
-            name: "gram".to_string(),
-            plural: "grams".to_string(),
-            abbreviation: "g".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 1.0,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Gramm,
-                    exp: 1,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create()
-        .add_si_prefixes(),
-    );
-
-    v.extend(
-        Unit {
-            name: "second".to_string(),
-            plural: "seconds".to_string(),
-            abbreviation: "s".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 1.0,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Second,
-                    exp: 1,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create()
-        .add_si_prefixes(),
-    );
-    
-    v.extend(
-        Unit {
-            name: "hertz".to_string(),
-            plural: "hertz".to_string(),
-            abbreviation: "Hz".to_string(),
-            valid_names: vec!["hz".to_string()],
-            si: UnitNumber {
-                num: 1.0,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Second,
-                    exp: -1,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create()
-        .add_si_prefixes(),
-    );
-
-    v.push(
-        Unit {
-            name: "minute".to_string(),
-            plural: "minutes".to_string(),
-            abbreviation: "min".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 60.0,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Second,
-                    exp: 1,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create(),
-    );
-
-    v.push(
-        Unit {
-            name: "hour".to_string(),
-            plural: "hours".to_string(),
-            abbreviation: "h".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 60.0 * 60.0,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Second,
-                    exp: 1,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create(),
-    );
-
-    v.push(
-        Unit {
-            name: "day".to_string(),
-            plural: "days".to_string(),
-            abbreviation: "d".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 60.0 * 60.0 * 24.0,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Second,
-                    exp: 1,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create(),
-    );
-
-    v.push(
-        Unit {
-            name: "week".to_string(),
-            plural: "weeks".to_string(),
-            abbreviation: "weeks".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 60.0 * 60.0 * 24.0 * 7.0,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Second,
-                    exp: 1,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create(),
-    );
-
-    v.push(
-        Unit {
-            name: "month".to_string(),
-            plural: "months".to_string(),
-            abbreviation: "mon".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 60.0 * 60.0 * 24.0 * 30.436875,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Second,
-                    exp: 1,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create(),
-    );
-
-    v.push(
-        Unit {
-            name: "year".to_string(),
-            plural: "years".to_string(),
-            abbreviation: "a".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 60.0 * 60.0 * 24.0 * 365.2425,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Second,
-                    exp: 1,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create(),
-    );
-
-    v.extend(
-        Unit {
-            name: "ampere".to_string(),
-            plural: "amperes".to_string(),
-            abbreviation: "A".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 1.0,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Ampere,
-                    exp: 1,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create()
-        .add_si_prefixes(),
-    );
-
-    v.extend(
-        Unit {
-            name: "volt".to_string(),
-            plural: "volts".to_string(),
-            abbreviation: "V".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 1000.0,
-                units: vec![
-                    UnitExp {
-                        unit: MetricBaseUnit::Gramm,
-                        exp: 1,
-                    },
-                    UnitExp {
-                        unit: MetricBaseUnit::Meter,
-                        exp: 2,
-                    },
-                    UnitExp {
-                        unit: MetricBaseUnit::Second,
-                        exp: -3,
-                    },
-                    UnitExp {
-                        unit: MetricBaseUnit::Ampere,
-                        exp: -1,
-                    },
-                ],
-            }
-            .cleaned(),
-            priority: 0.0,
-        }
-        .create()
-        .add_si_prefixes(),
-    );
-
-    v.extend(
-        Unit {
-            name: "watt".to_string(),
-            plural: "watts".to_string(),
-            abbreviation: "W".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 1000.0,
-                units: vec![
-                    UnitExp {
-                        unit: MetricBaseUnit::Gramm,
-                        exp: 1,
-                    },
-                    UnitExp {
-                        unit: MetricBaseUnit::Meter,
-                        exp: 2,
-                    },
-                    UnitExp {
-                        unit: MetricBaseUnit::Second,
-                        exp: -3,
-                    },
-                ],
-            }
-            .cleaned(),
-            priority: 0.0,
-        }
-        .create()
-        .add_si_prefixes(),
-    );
-
-    v.extend(
-        Unit {
-            name: "joule".to_string(),
-            plural: "joules".to_string(),
-            abbreviation: "J".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 1000.0,
-                units: vec![
-                    UnitExp {
-                        unit: MetricBaseUnit::Gramm,
-                        exp: 1,
-                    },
-                    UnitExp {
-                        unit: MetricBaseUnit::Meter,
-                        exp: 2,
-                    },
-                    UnitExp {
-                        unit: MetricBaseUnit::Second,
-                        exp: -2,
-                    },
-                ],
-            }
-            .cleaned(),
-            priority: 0.0,
+/// whether `name` is read as something other than a variable, like `and`, `of` or `today`
+pub fn is_keyword(name: &str) -> bool {
+    ATOMIC.contains_key(name) || parse_datetime_literal(name).is_some()
+}
+/// numbers, whitespace, strings and dates can be longer than any name
+fn may_be_long(first: char) -> bool {
+    first.is_ascii_digit() || first.is_whitespace() || first == '.' || first == '"'
+}
+/// the longest unit, variable or keyword, so longer words need not be looked up
+fn longest_word(units: &UnitRegistry, env: &Environment) -> usize {
+    let variables = env.variables.keys().map(|k| k.chars().count()).max().unwrap_or(0);
+    let keywords = ATOMIC.keys().map(|k| k.chars().count()).max().unwrap_or(0);
+    units.longest_name().max(variables).max(keywords).max("today".len())
+}
+/// prefixed and rarely used units are less likely to be meant, e.g. milli-inch for `min`
+fn unit_plausibility(u: &Unit) -> f32 {
+    let prefixed = if u.name != u.base_name { -1.0 } else { 0.0 };
+    prefixed + u.priority.max(-3.0) * 0.1
+}
+/// whether two adjacent tokens multiply a constant with a unit, like `G bit`
+fn implicit_constant(a: &Token, b: &Token) -> bool {
+    match (a, b) {
+        (Token::Unit(_, Some(a)), Token::Unit(_, Some(b))) => {
+            a.priority <= NEVER_CHOSEN || b.priority <= NEVER_CHOSEN
         }
-        .create()
-        .add_si_prefixes(),
-    );
-
-    v.extend(
-        Unit {
-            name: "newton".to_string(),
-            plural: "newtons".to_string(),
-            abbreviation: "N".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 1000.0,
-                units: vec![
-                    UnitExp {
-                        unit: MetricBaseUnit::Gramm,
-                        exp: 1,
-                    },
-                    UnitExp {
-                        unit: MetricBaseUnit::Meter,
-                        exp: 1,
-                    },
-                    UnitExp {
-                        unit: MetricBaseUnit::Second,
-                        exp: -2,
-                    },
-                ],
+        _ => false,
+    }
+}
+/// every plausible tokenization of `input` with a score, the most plausible first
+pub fn lex_all(input: String, units: &UnitRegistry, env: &Environment) -> Vec<(Vec<Token>, f32)> {
+    // partial tokenizations kept per position
+    const BEAM: usize = 16;
+    let chars = input.chars().collect::<Vec<char>>();
+    let longest = longest_word(units, env);
+    let mut best: Vec<Vec<(Vec<Token>, f32)>> = vec![Vec::new(); chars.len() + 1];
+    best[0].push((Vec::new(), 0.0));
+    for start in 0..chars.len() {
+        best[start].sort_by(|a, b| b.1.total_cmp(&a.1));
+        best[start].truncate(BEAM);
+        // numbers are never split
+        if best[start].is_empty()
+            || (start > 0 && chars[start - 1].is_ascii_digit() && chars[start].is_ascii_digit())
+        {
+            continue;
+        }
+        let last = if may_be_long(chars[start]) {
+            chars.len()
+        } else {
+            chars.len().min(start + longest)
+        };
+        for end in start + 1..=last {
+            // an unknown is a whole word, not the `k` of `km`
+            let whole_word = !chars.get(end).is_some_and(|c| c.is_alphabetic())
+                && (start == 0 || !chars[start - 1].is_alphabetic());
+            for (tokens, score) in get_tokens(chars[start..end].iter().collect(), units, env) {
+                if !whole_word && tokens.iter().any(|t| matches!(t, Token::Unknown(_))) {
+                    continue;
+                }
+                // fewer, longer tokens are more plausible
+                let score = if tokens.is_empty() { score } else { score - 1.0 };
+                for (previous, previous_score) in best[start].clone() {
+                    let score = match (previous.last(), tokens.first()) {
+                        (Some(a), Some(b)) if implicit_constant(a, b) => score - IMPLICIT_CONSTANT_PENALTY,
+                        _ => score,
+                    };
+                    let mut joined = previous;
+                    joined.extend(tokens.iter().cloned());
+                    if !best[end].iter().any(|(t, _)| *t == joined) {
+                        best[end].push((joined, previous_score + score));
+                    }
+                }
             }
-            .cleaned(),
-            priority: 0.0,
         }
-        .create()
-        .add_si_prefixes(),
-    );
-
-    v.extend(
-        Unit {
-            name: "kelvin".to_string(),
-            plural: "kelvins".to_string(),
-            abbreviation: "K".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 1.0,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Kelvin,
-                    exp: 1,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create()
-        .add_si_prefixes(),
-    );
-
-    v.extend(
-        Unit {
-            name: "mole".to_string(),
-            plural: "moles".to_string(),
-            abbreviation: "mol".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 1.0,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Mole,
-                    exp: 1,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create()
-        .add_si_prefixes(),
-    );
-
-    v.extend(
-        Unit {
-            name: "candela".to_string(),
-            plural: "candelas".to_string(),
-            abbreviation: "cd".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 1.0,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Candela,
-                    exp: 1,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create()
-        .add_si_prefixes(),
-    );
-
-    v.extend(
-        Unit {
-            name: "liter".to_string(),
-            plural: "liters".to_string(),
-            abbreviation: "L".to_string(),
-            valid_names: vec!["l".to_string()],
-            si: UnitNumber {
-                num: 0.001,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Meter,
-                    exp: 3,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create()
-        .add_si_prefixes(),
-    );
-
-    v.extend(
-        Unit {
-            name: "byte".to_string(),
-            plural: "bytes".to_string(),
-            abbreviation: "B".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 1.0,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Byte,
-                    exp: 1,
-                }],
-            },
-            priority: 0.0,
-        }
-        .create()
-        .add_si_prefixes(),
-    );
-
-    v.extend(
-        Unit {
-            name: "bit".to_string(),
-            plural: "bits".to_string(),
-            abbreviation: "b".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 0.125,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Byte,
-                    exp: 1,
-                }],
-            },
-            priority: 0.9,
-        }
-        .create()
-        .add_si_prefixes(),
-    );
-
-    // IMPERIAL
-
-    v.push(
-        Unit {
-            name: "inch".to_string(),
-            plural: "inches".to_string(),
-            abbreviation: "in".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 0.0254,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Meter,
-                    exp: 1,
-                }],
-            },
-            priority: -3.0,
-        }
-        .create(),
-    );
-
-    v.push(
-        Unit {
-            name: "foot".to_string(),
-            plural: "feet".to_string(),
-            abbreviation: "ft".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 0.3048,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Meter,
-                    exp: 1,
-                }],
-            },
-            priority: -3.0,
-        }
-        .create(),
-    );
-
-    v.push(
-        Unit {
-            name: "yard".to_string(),
-            plural: "yards".to_string(),
-            abbreviation: "yd".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 0.9144,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Meter,
-                    exp: 1,
-                }],
-            },
-            priority: -3.0,
-        }
-        .create(),
-    );
-
-    v.push(
-        Unit {
-            name: "mile".to_string(),
-            plural: "miles".to_string(),
-            abbreviation: "mi".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 1609.344,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Meter,
-                    exp: 1,
-                }],
-            },
-            priority: -3.0,
-        }
-        .create(),
-    );
-
-    v.push(
-        Unit {
-            name: "ounce".to_string(),
-            plural: "ounces".to_string(),
-            abbreviation: "oz".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 28.349523125,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Gramm,
-                    exp: 1,
-                }],
-            },
-            priority: -3.0,
-        }
-        .create(),
-    );
-
-    v.push(
-        Unit {
-            name: "pound".to_string(),
-            plural: "pounds".to_string(),
-            abbreviation: "lb".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 453.59237,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Gramm,
-                    exp: 1,
-                }],
-            },
-            priority: -3.0,
-        }
-        .create(),
-    );
-
-    v.push(
-        Unit {
-            name: "gallon".to_string(),
-            plural: "gallons".to_string(),
-            abbreviation: "gal".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 0.003785411784,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Meter,
-                    exp: 3,
-                }],
-            },
-            priority: -3.0,
-        }
-        .create(),
-    );
-
-    v.push(
-        Unit {
-            name: "pint".to_string(),
-            plural: "pints".to_string(),
-            abbreviation: "pt".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 0.000473176473,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Meter,
-                    exp: 3,
-                }],
-            },
-            priority: -3.0,
-        }
-        .create(),
-    );
-
-    v.push(
-        Unit {
-            name: "square foot".to_string(),
-            plural: "square feet".to_string(),
-            abbreviation: "sqft".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 0.09290304,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Meter,
-                    exp: 2,
-                }],
-            },
-            priority: -3.0,
-        }
-        .create(),
-    );
-
-    v.push(
-        Unit {
-            name: "acre".to_string(),
-            plural: "acres".to_string(),
-            abbreviation: "ac".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 4046.8564224,
-                units: vec![UnitExp {
-                    unit: MetricBaseUnit::Meter,
-                    exp: 2,
-                }],
-            },
-            priority: -3.0,
-        }
-        .create(),
-    );
-
-    v.push(
-        Unit {
-            name: "pound-force".to_string(),
-            plural: "pound-force".to_string(),
-            abbreviation: "lbf".to_string(),
-            valid_names: Vec::new(),
-            si: UnitNumber {
-                num: 4448.2216152605,
-                units: vec![
-                    UnitExp {
-                        unit: MetricBaseUnit::Gramm,
-                        exp: 1,
-                    },
-                    UnitExp {
-                        unit: MetricBaseUnit::Meter,
-                        exp: 1,
-                    },
-                    UnitExp {
-                        unit: MetricBaseUnit::Second,
-                        exp: -2,
-                    },
-                ],
-            },
-            priority: -3.0,
-        }
-        .create(),
-    );
-
-    v
+    }
+    let mut all = best.pop().unwrap_or_default();
+    all.sort_by(|a, b| b.1.total_cmp(&a.1));
+    all.truncate(BEAM);
+    all
+}
+pub fn get_token(s: String, units: &UnitRegistry, env: &Environment) -> Option<Vec<Token>> {
+    get_tokens(s, units, env).into_iter().next().map(|(t, _)| t)
+}
+/// the built-in units and constants followed by the user's units file, if there is one
+pub fn get_units() -> UnitRegistry {
+    let mut units = UnitRegistry::default();
+    if let Err(e) = load_unit_definitions(BUILTIN_UNITS, &mut units) {
+        eprintln!("could not load the built-in units: {e}");
+    }
+    if let Err(e) = load_unit_definitions(CONSTANTS, &mut units) {
+        eprintln!("could not load the constants: {e}");
+    }
+    if let Ok(s) = std::fs::read_to_string(USER_UNITS_FILE)
+        && let Err(e) = load_unit_definitions(&s, &mut units)
+    {
+        eprintln!("could not load {USER_UNITS_FILE}: {e}");
+    }
+    units
 }
+/// the short and long form of each SI prefix and its power of ten
+const SI_PREFIXES: &[(&str, &str, i32)] = &[
+    ("y", "yocto", -24),
+    ("z", "zepto", -21),
+    ("a", "atto", -18),
+    ("f", "fempto", -15),
+    ("p", "pico", -12),
+    ("n", "nano", -9),
+    ("µ", "micro", -6),
+    ("m", "milli", -3),
+    ("c", "centi", -2),
+    ("d", "deci", -1),
+    ("k", "kilo", 3),
+    ("M", "mega", 6),
+    ("G", "giga", 9),
+    ("T", "tera", 12),
+    ("P", "peta", 15),
+    ("E", "exa", 18),
+    ("Z", "zetta", 21),
+    ("Y", "yotta", 24),
+];
+/// the binary prefixes, each 1024 times the one before
+const IEC_PREFIXES: &[(&str, &str)] = &[
+    ("Ki", "kibi"),
+    ("Mi", "mebi"),
+    ("Gi", "gibi"),
+    ("Ti", "tebi"),
+    ("Pi", "pebi"),
+    ("Ei", "exbi"),
+];
 #[derive(Clone, PartialEq)]
 pub struct Unit {
     pub name: String,
@@ -812,6 +388,10 @@ pub struct Unit {
     pub valid_names: Vec<String>,
     pub si: UnitNumber,
     pub priority: f32,
+    /// the name without any prefix, e.g. `byte` for kibibytes
+    pub base_name: String,
+    /// whether the unit has a binary (IEC) prefix
+    pub binary: bool,
 }
 impl Unit {
     pub fn create(mut self) -> Self {
@@ -820,347 +400,56 @@ impl Unit {
         self.valid_names.push(self.plural.clone());
         self
     }
-    pub fn add_si_prefixes(self) -> Vec<Self> {
-        vec![
-            Unit {
-                name: format!("yocto{}", &self.name),
-                plural: format!("yocto{}", &self.plural),
-                abbreviation: format!("y{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("y{}", n),
-                        _ => format!("yocto{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e-24,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("zepto{}", &self.name),
-                plural: format!("zepto{}", &self.plural),
-                abbreviation: format!("z{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("z{}", n),
-                        _ => format!("zepto{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e-21,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("atto{}", &self.name),
-                plural: format!("atto{}", &self.plural),
-                abbreviation: format!("a{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("a{}", n),
-                        _ => format!("atto{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e-18,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("fempto{}", &self.name),
-                plural: format!("fempto{}", &self.plural),
-                abbreviation: format!("f{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("f{}", n),
-                        _ => format!("fempto{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e-15,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("pico{}", &self.name),
-                plural: format!("pico{}", &self.plural),
-                abbreviation: format!("p{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("p{}", n),
-                        _ => format!("pico{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e-12,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("nano{}", &self.name),
-                plural: format!("nano{}", &self.plural),
-                abbreviation: format!("n{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("n{}", n),
-                        _ => format!("nano{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e-9,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("micro{}", &self.name),
-                plural: format!("micro{}", &self.plural),
-                abbreviation: format!("µ{}", &self.abbreviation),
-                valid_names: vec![
-                    format!("mu{}", &self.abbreviation),
-                    format!("micro{}", &self.plural),
-                    format!("micro{}", &self.name),
-                ],
-                si: UnitNumber {
-                    num: self.si.num * 1e-6,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("milli{}", &self.name),
-                plural: format!("milli{}", &self.plural),
-                abbreviation: format!("m{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("m{}", n),
-                        _ => format!("milli{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e-3,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("centi{}", &self.name),
-                plural: format!("centi{}", &self.plural),
-                abbreviation: format!("c{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("c{}", n),
-                        _ => format!("centi{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e-2,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("deci{}", &self.name),
-                plural: format!("deci{}", &self.plural),
-                abbreviation: format!("d{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("d{}", n),
-                        _ => format!("deci{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e-1,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("{}", &self.name),
-                plural: format!("{}", &self.plural),
-                abbreviation: format!("{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("{}", n),
-                        _ => format!("{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("kilo{}", &self.name),
-                plural: format!("kilo{}", &self.plural),
-                abbreviation: format!("k{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("k{}", n),
-                        _ => format!("kilo{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e3,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("mega{}", &self.name),
-                plural: format!("mega{}", &self.plural),
-                abbreviation: format!("M{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("M{}", n),
-                        _ => format!("mega{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e6,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("giga{}", &self.name),
-                plural: format!("giga{}", &self.plural),
-                abbreviation: format!("G{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("G{}", n),
-                        _ => format!("giga{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e9,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("tera{}", &self.name),
-                plural: format!("tera{}", &self.plural),
-                abbreviation: format!("T{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("T{}", n),
-                        _ => format!("tera{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e12,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("peta{}", &self.name),
-                plural: format!("peta{}", &self.plural),
-                abbreviation: format!("P{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("P{}", n),
-                        _ => format!("peta{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e15,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("exa{}", &self.name),
-                plural: format!("exa{}", &self.plural),
-                abbreviation: format!("E{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("E{}", n),
-                        _ => format!("exa{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e18,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("zetta{}", &self.name),
-                plural: format!("zetta{}", &self.plural),
-                abbreviation: format!("Z{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("Z{}", n),
-                        _ => format!("zetta{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e21,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-            Unit {
-                name: format!("yotta{}", &self.name),
-                plural: format!("yotta{}", &self.plural),
-                abbreviation: format!("Y{}", &self.abbreviation),
-                valid_names: self
-                    .valid_names
-                    .iter()
-                    .map(|n| match n.len() {
-                        ..4 => format!("Y{}", n),
-                        _ => format!("yotta{}", n),
-                    })
-                    .collect::<Vec<String>>(),
-                si: UnitNumber {
-                    num: self.si.num * 1e24,
-                    units: self.si.units.clone(),
-                },
-                priority: self.priority,
-            },
-        ]
+    /// the unit with a prefix; the abbreviation takes the short form and the name and plural the
+    /// long one, aliases like `ohm` take both, for `kohm` and `kiloohm`
+    fn prefixed(&self, aliases: &[String], short: &str, long: &str, factor: &BigRational, binary: bool) -> Self {
+        let mut valid_names = vec![
+            format!("{short}{}", self.abbreviation),
+            format!("{long}{}", self.name),
+            format!("{long}{}", self.plural),
+        ];
+        for alias in aliases {
+            valid_names.push(format!("{short}{alias}"));
+            valid_names.push(format!("{long}{alias}"));
+        }
+        valid_names.dedup();
+        Unit {
+            name: format!("{long}{}", self.name),
+            plural: format!("{long}{}", self.plural),
+            abbreviation: format!("{short}{}", self.abbreviation),
+            valid_names,
+            si: self.si.scaled(factor),
+            priority: self.priority,
+            base_name: self.base_name.clone(),
+            binary,
+        }
+    }
+    /// yocto to yotta, with the unprefixed unit in between
+    pub fn add_si_prefixes(self, aliases: &[String]) -> Vec<Self> {
+        let mut units = Vec::new();
+        for (short, long, exponent) in SI_PREFIXES {
+            let factor = BigRational::from_integer(BigInt::from(10)).pow(*exponent);
+            let mut unit = self.prefixed(aliases, short, long, &factor, false);
+            // `mum` for micrometers, as µ is hard to type
+            if *short == "µ" {
+                unit.valid_names.push(format!("mu{}", self.abbreviation));
+            }
+            units.push(unit);
+            if *exponent == -1 {
+                units.push(self.clone());
+            }
+        }
+        units
+    }
+    /// kibi to exbi, for data units
+    pub fn add_iec_prefixes(self, aliases: &[String]) -> Vec<Self> {
+        IEC_PREFIXES
+            .iter()
+            .enumerate()
+            .map(|(i, (short, long))| {
+                let factor = BigRational::from_integer(BigInt::from(1024).pow(i as u32 + 1));
+                self.prefixed(aliases, short, long, &factor, true)
+            })
+            .collect()
     }
 }
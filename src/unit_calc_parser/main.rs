@@ -1,145 +1,1063 @@
-use arboard::Clipboard;
+use std::sync::{Arc, RwLock};
+
 use async_trait::async_trait;
+use egui::CollapsingHeader;
+use jiff::{Zoned, tz::TimeZone};
+use num_bigint::BigInt;
+use num_rational::{BigRational, Rational64};
+use num_traits::{FromPrimitive, Signed, Zero};
+use regex::Regex;
 use tokio::sync::mpsc;
 
 use crate::{
+    clipboard::copy_or_report,
     query_manager::{ListEntry, QueryParser},
     unit_calc_parser::{
-        lexer::{get_units, lex},
-        parser::{UnitCalculation, parse_unit_conversion},
-        unit_number_parser::superscript,
+        datetime::{datetime_alternatives, format_datetime, parse_time_zone},
+        dimensions::{evaluate_units, in_kilograms, matching_quantities, matching_units, signature},
+        environment::Environment,
+        exact::{to_exact_string, to_terminating_decimal},
+        history::History,
+        lexer::{Token, Unit, get_units, is_keyword, lex, lex_all},
+        number_format::{NumberFormat, Notation, format_full},
+        programmer::{
+            Base, IntegerMode, execute_wrapped, format_integer, parse_integer_switch, parse_radix_literal,
+            wrap,
+        },
+        parser::{UnitConversion, parse_unit_conversion},
+        simplify::{NEVER_CHOSEN, Simplification, describe, simplify},
+        solver::solve,
+        statistics::{AGGREGATES, aggregate},
+        unit_number_parser::{UnitNumber, unit_exp_to_superscript_exp, with_exponent},
+        unit_definitions::{CONSTANTS, UnitDefinition},
+        unit_registry::UnitRegistry,
+        value::Value,
     },
 };
 
+/// how many readings of an ambiguous query are shown
+const MAX_INTERPRETATIONS: usize = 4;
+/// readings at least this much less plausible than the best one are not shown
+const MAX_SCORE_DISTANCE: f32 = 1.0;
+/// how many units the result of the best reading is shown in, the most readable one included
+const MAX_RUNNERS_UP: usize = 3;
+/// units this much less readable than the best ones are not offered
+const MAX_RUNNER_UP_DISTANCE: f64 = 3.0;
+
 #[derive(Clone)]
-pub struct UnitCalcParser {}
+pub struct UnitCalcParser {
+    units: Arc<UnitRegistry>,
+    environment: Arc<RwLock<Environment>>,
+    format: Arc<NumberFormat>,
+    history: Arc<RwLock<History>>,
+}
 impl Default for UnitCalcParser {
     fn default() -> Self {
-        Self {}
+        Self {
+            units: Arc::new(get_units()),
+            environment: Arc::new(RwLock::new(Environment::load())),
+            format: Arc::new(NumberFormat::load()),
+            history: Arc::new(RwLock::new(History::load())),
+        }
     }
 }
 #[async_trait]
 impl QueryParser for UnitCalcParser {
     async fn parse(&self, query: String, resopnse: mpsc::Sender<ListEntry>) {
         let len = query.len();
-        let (text, priority) = match execute_unit_str(query) {
-            Ok(v) => {
-                (v, (len as f32))
+        let env = self.environment.read().unwrap().clone();
+        if query.trim() == "=" {
+            self.show_history(&env, resopnse).await;
+            return;
+        }
+        let expression = query.clone();
+        let help = match parse_constants_query(&query) {
+            Some(filter) => Some(constants_help(&filter)),
+            // `what is 2 + 2` without units is just a calculation
+            None => parse_dimension_query(&query)
+                .and_then(|expression| dimension_help(&expression, &self.units, &env).ok())
+                .filter(|rows| !rows.is_empty()),
+        };
+        if let Some(help) = help {
+            for (i, (text, detail)) in help.into_iter().enumerate() {
+                let definition = text.split_once(" = ").map_or(text.clone(), |(_, d)| d.to_string());
+                resopnse
+                    .send(ListEntry {
+                        layout_fn: Box::new(move |ui| {
+                            ui.label(&text);
+                            ui.weak(&detail);
+                        }),
+                        execute: Some(Box::new(move || {
+                            copy_or_report(&definition);
+                        })),
+                        priority: len as f32 - i as f32 * 0.01,
+                        fill_query: None,
+                    })
+                    .await
+                    .unwrap();
+            }
+            return;
+        }
+        let query = parse_dimension_query(&query).unwrap_or(query);
+        let (query, options) = parse_switches(&query);
+        let (assignment, input) = match parse_assignment(&query) {
+            Some((name, expr)) => (Some(name), expr),
+            None => (None, query),
+        };
+        let result = match &assignment {
+            Some(name) => check_variable_name(name, &self.units)
+                .and_then(|_| execute_unit_str(input, &self.units, &env, options, &self.format)),
+            None => execute_unit_str(input, &self.units, &env, options, &self.format),
+        };
+        let results = match result {
+            // only the most plausible reading is stored
+            Ok(mut results) => match &assignment {
+                Some(name) => match results.swap_remove(0) {
+                    r @ CalcResult { value: Some(_), .. } => vec![(
+                        CalcResult {
+                            text: format!("{name} = {}", r.text),
+                            alternatives: Vec::new(),
+                            ..r
+                        },
+                        len as f32,
+                    )],
+                    _ => vec![(
+                        CalcResult {
+                            text: "error: only numbers can be stored".to_string(),
+                            ..Default::default()
+                        },
+                        -1.0,
+                    )],
+                },
+                None => results
+                    .into_iter()
+                    .enumerate()
+                    .map(|(rank, r)| (r, len as f32 - rank as f32))
+                    .collect(),
             },
-            Err(e) => (format!("error: {e}"),-1.0),
+            Err(e) => vec![(
+                CalcResult {
+                    text: format!("error: {e}"),
+                    ..Default::default()
+                },
+                -1.0,
+            )],
         };
-        let text2 = text.clone();
-        resopnse
-            .send(ListEntry {
-                layout_fn: Box::new(move |ui| {
-                    ui.label(format!("{}", &text));
-                }),
-                execute: Some(Box::new(move || {
-                    Clipboard::new().unwrap().set_text(&text2).unwrap();
-                })),
-                priority: priority,
-            })
-            .await
-            .unwrap();
-    }
-}
-pub fn execute_unit_str(input: String) -> Result<String, String> {
-    let units = get_units();
-    let tokens = lex(input, &units).ok_or("lexing failed!".to_string())?;
-    let ast = parse_unit_conversion(tokens)?;
-    let (un, mut u, tu) = ast.execute()?;
-    let mut exponent = 1;
-    if u.is_none()&&tu.is_none() {
-        let mut best_score = f64::NEG_INFINITY;
-        for unit in units {
-            let cleaned = unit.si.cleaned();
-            if let Some(log) = un.log(&cleaned) {
-                let nun = cleaned.pow_i64(log);
-                let unit_number = format!(
-                    "{:.5}",
-                    UnitCalculation::Div(
-                        Box::new(UnitCalculation::Number(un.clone())),
-                        Box::new(UnitCalculation::Number(nun.clone()))
-                    )
-                    .execute()
-                    .unwrap()
-                    .num
-                )
-                .trim_end_matches('0')
-                .trim_end_matches('.')
-                .to_string();
-                let mut score = (-(unit_number.len() as f64)
-                    - (unit_number.len() as f64
-                        - unit_number
-                            .chars()
-                            .enumerate()
-                            .find(|x| x.1 == '.')
-                            .unwrap_or((unit_number.to_string().len(), '.'))
-                            .0 as f64))
-                    + (unit.priority as f64);
-                if log != 1 {
-                    score -= 0.1;
+        for (result, priority) in results {
+            let CalcResult {
+                text,
+                value,
+                alternatives,
+                copy_forms,
+                interpretation,
+                steps,
+            } = result;
+            let text2 = text.clone();
+            let value2 = value.clone();
+            let assignment = assignment.clone();
+            let assignment2 = assignment.clone();
+            let store_name = env.next_free_name();
+            let mut stored = false;
+            let environment = self.environment.clone();
+            let environment2 = self.environment.clone();
+            let history = self.history.clone();
+            let expression = expression.clone();
+            resopnse
+                .send(ListEntry {
+                    layout_fn: Box::new(move |ui| {
+                        ui.vertical(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.label(&text);
+                                for alternative in &alternatives {
+                                    if ui.small_button(alternative).on_hover_text("copy").clicked() {
+                                        copy_or_report(alternative);
+                                    }
+                                }
+                                if !copy_forms.is_empty() {
+                                    ui.menu_button("copy as", |ui| {
+                                        for (label, form) in &copy_forms {
+                                            if ui.button(format!("{label}: {form}")).clicked() {
+                                                copy_or_report(form);
+                                                ui.close();
+                                            }
+                                        }
+                                    });
+                                }
+                                if let Some(v) = &value
+                                    && assignment.is_none()
+                                {
+                                    if stored {
+                                        ui.weak(format!("stored as {store_name}"));
+                                    } else if ui.small_button(format!("store as {store_name}")).clicked() {
+                                        environment.write().unwrap().set(store_name.clone(), v.clone());
+                                        stored = true;
+                                    }
+                                }
+                            });
+                            if !interpretation.is_empty() {
+                                ui.weak(&interpretation);
+                            }
+                            // a single step would only repeat the result
+                            if steps.len() > 1 {
+                                CollapsingHeader::new("steps").id_salt((&interpretation, &text)).show(ui, |ui| {
+                                    for (expression, value) in &steps {
+                                        ui.label(format!("{expression} = {value}"));
+                                    }
+                                });
+                            }
+                        });
+                    }),
+                    execute: Some(Box::new(move || {
+                        if priority >= 0.0 {
+                            history.write().unwrap().add(expression.clone(), text2.clone());
+                        }
+                        let mut env = environment2.write().unwrap();
+                        env.ans = value2.clone();
+                        match (&assignment2, &value2) {
+                            (Some(name), Some(v)) => env.set(name.clone(), v.clone()),
+                            _ => copy_or_report(&text2),
+                        }
+                    })),
+                    priority,
+                    fill_query: None,
+                })
+                .await
+                .unwrap();
+        }
+    }
+}
+impl UnitCalcParser {
+    /// executed calculations for the query `=`, pinned ones first and evaluated again with the current variables
+    async fn show_history(&self, env: &Environment, resopnse: mpsc::Sender<ListEntry>) {
+        let mut entries = self.history.read().unwrap().entries.clone();
+        // the sort is stable, so both groups stay most recent first
+        entries.sort_by_key(|e| !e.pinned);
+        let count = entries.len();
+        for (i, mut entry) in entries.into_iter().enumerate() {
+            let result = match entry.pinned {
+                true => evaluate_expression(&entry.expression, &self.units, env, &self.format),
+                false => entry.result.clone(),
+            };
+            let history = self.history.clone();
+            let expression = entry.expression.clone();
+            resopnse
+                .send(ListEntry {
+                    layout_fn: Box::new(move |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{} → {result}", entry.expression));
+                            ui.weak(&entry.timestamp);
+                            let pin = if entry.pinned { "unpin" } else { "pin" };
+                            if ui.small_button(pin).clicked() {
+                                history.write().unwrap().toggle_pin(&entry.expression);
+                                entry.pinned = !entry.pinned;
+                            }
+                        });
+                    }),
+                    execute: None,
+                    priority: count as f32 - i as f32 * 0.01,
+                    fill_query: Some(expression),
+                })
+                .await
+                .unwrap();
+        }
+    }
+}
+/// the text of the most plausible result of a query, as shown for pinned history entries
+fn evaluate_expression(query: &str, units: &UnitRegistry, env: &Environment, format: &NumberFormat) -> String {
+    let query = parse_dimension_query(query).unwrap_or(query.to_string());
+    let (query, options) = parse_switches(&query);
+    let input = parse_assignment(&query).map_or(query, |(_, expr)| expr);
+    match execute_unit_str(input, units, env, options, format) {
+        Ok(results) => results.into_iter().next().map(|r| r.text).unwrap_or_default(),
+        Err(e) => format!("error: {e}"),
+    }
+}
+/// `constants` or `constants mass` shows the built-in constants
+pub fn parse_constants_query(query: &str) -> Option<String> {
+    let re = Regex::new(r"^\s*(?:help\s+)?constants\b\s*(.*?)\s*$").unwrap();
+    re.captures(query).map(|captures| captures[1].to_lowercase())
+}
+/// each constant whose name or symbol contains `filter`, with its value and where it comes from
+pub fn constants_help(filter: &str) -> Vec<(String, String)> {
+    let definitions: Vec<UnitDefinition> = serde_json::from_str(CONSTANTS).unwrap_or_default();
+    definitions
+        .into_iter()
+        .filter(|d| {
+            d.name.to_lowercase().contains(filter) || d.abbreviation.to_lowercase().contains(filter)
+        })
+        .map(|d| {
+            let mut names: Vec<String> = vec![d.name];
+            for alias in d.aliases {
+                if !names.iter().any(|n| n.eq_ignore_ascii_case(&alias)) {
+                    names.push(alias);
                 }
-                if score > best_score && unit_number != "0".to_string() {
-                    u = Some(unit);
-                    best_score = score;
-                    exponent = log;
+            }
+            (
+                format!("{} = {}", d.abbreviation, d.definition.unwrap_or_default()),
+                format!("{}; {}", names.join(", "), d.source.unwrap_or_default()),
+            )
+        })
+        .collect()
+}
+/// `dim kg m^2 s^-3` or `what is kg m^2 s^-3` asks what a unit expression measures
+pub fn parse_dimension_query(query: &str) -> Option<String> {
+    let re = Regex::new(r"^\s*(?:dim|what\s+is)\s+(.+?)\s*\??\s*$").unwrap();
+    re.captures(query).map(|captures| captures[1].to_string())
+}
+/// the dimension of `expression`, the units with that dimension and the quantities measured in it
+pub fn dimension_help(expression: &str, units: &UnitRegistry, env: &Environment) -> Result<Vec<(String, String)>, String> {
+    let un = evaluate_units(expression, units, env)?;
+    if un.units.is_empty() {
+        return Ok(Vec::new());
+    }
+    let (base_units, symbols) = signature(&un);
+    let mut rows = vec![(base_units, format!("dimension {symbols}"))];
+    let named = matching_units(&un, units)
+        .into_iter()
+        .map(|u| format!("{} ({})", u.name, u.abbreviation))
+        .collect::<Vec<String>>();
+    if !named.is_empty() {
+        rows.push((named.join(", "), "units with this dimension".to_string()));
+    }
+    let quantities = matching_quantities(&un, units);
+    if !quantities.is_empty() {
+        rows.push((quantities.join(", "), "quantities with this dimension".to_string()));
+    }
+    Ok(rows)
+}
+/// splits `let name = expression` into the name and the expression
+pub fn parse_assignment(query: &str) -> Option<(String, String)> {
+    let re = Regex::new(r"^\s*let\s+([^\W\d]\w*)\s*=\s*(.+)$").unwrap();
+    let captures = re.captures(query)?;
+    Some((captures[1].to_string(), captures[2].to_string()))
+}
+/// `expression = exact` shows exact fractions instead of rounded decimals
+pub fn parse_exact_switch(query: &str) -> (String, bool) {
+    let re = Regex::new(r"^(.*?)\s*=\s*exact\s*$").unwrap();
+    match re.captures(query) {
+        Some(captures) => (captures[1].to_string(), true),
+        None => (query.to_string(), false),
+    }
+}
+/// `expression to all` lists the result in every unit it can be converted to
+pub fn parse_all_units_switch(query: &str) -> (String, bool) {
+    let re = Regex::new(r"^(.*?)\s+(?:in|to|as|=>|->)\s+all\s*$").unwrap();
+    match re.captures(query) {
+        Some(captures) => (captures[1].to_string(), true),
+        None => (query.to_string(), false),
+    }
+}
+/// switches at the end of a query that change how the result is shown
+#[derive(Clone, Copy, Default)]
+pub struct CalcOptions {
+    pub exact: bool,
+    pub integer: Option<IntegerMode>,
+    pub all_units: bool,
+}
+pub fn parse_switches(query: &str) -> (String, CalcOptions) {
+    let (query, exact) = parse_exact_switch(query);
+    let (query, integer) = parse_integer_switch(&query);
+    let (query, all_units) = parse_all_units_switch(&query);
+    (query, CalcOptions { exact, integer, all_units })
+}
+fn check_variable_name(name: &str, units: &[Unit]) -> Result<(), String> {
+    if name == "ans" {
+        return Err("ans cannot be assigned".to_string());
+    }
+    if units.iter().any(|u| u.valid_names.iter().any(|n| n == name)) {
+        return Err(format!("{name} is already a unit"));
+    }
+    // keywords are lexed before variables, so the variable could never be read back
+    if is_keyword(name) {
+        return Err(format!("{name} is a keyword"));
+    }
+    Ok(())
+}
+#[derive(Default)]
+pub struct CalcResult {
+    pub text: String,
+    /// the result in SI units, `None` if it is not a number
+    pub value: Option<UnitNumber>,
+    /// the same result in other notations, offered as extra copy actions
+    pub alternatives: Vec<String>,
+    /// the number, its unit symbols, LaTeX and other forms for the copy menu, with a label each
+    pub copy_forms: Vec<(String, String)>,
+    /// the query as it was understood, with every unit resolved
+    pub interpretation: String,
+    /// sub-expressions and their values in SI units
+    pub steps: Vec<(String, String)>,
+}
+/// the results of every interpretation of `input` that could be calculated, the most plausible first
+pub fn execute_unit_str(
+    input: String,
+    units: &UnitRegistry,
+    env: &Environment,
+    options: CalcOptions,
+    format: &NumberFormat,
+) -> Result<Vec<CalcResult>, String> {
+    if let Some((expr, time_zone)) = split_time_zone_switch(&input)
+        && let Ok(Value::DateTime(d)) = evaluate_str(expr, units, env)
+    {
+        return Ok(vec![datetime_result(d.with_time_zone(time_zone))]);
+    }
+    let input = parse_pasted_list(&input).unwrap_or(input);
+    let mut results: Vec<CalcResult> = Vec::new();
+    // other units for the best reading, shown after all readings
+    let mut runners_up = Vec::new();
+    let mut best_score = None;
+    let mut error = None;
+    // a conversion that was understood but failed, e.g. `1 lm in cd`, is not silently
+    // replaced by a reading with inches
+    let mut conversion_error = None;
+    let mut converted = false;
+    for (tokens, score) in lex_all(input, units, env) {
+        if best_score.is_some_and(|best| score <= best - MAX_SCORE_DISTANCE) {
+            break;
+        }
+        let ast = match parse_unit_conversion(tokens.clone()) {
+            Ok(ast) => ast,
+            Err(e) => {
+                error.get_or_insert(e);
+                continue;
+            }
+        };
+        let conversion = ast.is_conversion();
+        match execute_tokens(&tokens, ast, units, options, format) {
+            Ok(rs) => {
+                converted |= conversion;
+                let mut rs = rs.into_iter();
+                let Some(r) = rs.next() else {
+                    continue;
+                };
+                if best_score.is_none() {
+                    runners_up.extend(rs);
+                }
+                best_score.get_or_insert(score);
+                if !results.iter().any(|o| o.text == r.text) {
+                    results.push(r);
                 }
             }
+            Err(e) => {
+                if conversion {
+                    conversion_error.get_or_insert(e.clone());
+                }
+                error.get_or_insert(e);
+            }
+        }
+        if results.len() >= MAX_INTERPRETATIONS {
+            break;
         }
     }
-    if let Some(u) = u {
-        let unum=UnitCalculation::Div(
-                Box::new(UnitCalculation::Number(un.clone())),
-                Box::new(UnitCalculation::Number(u.si.pow_i64(exponent).clone()))
-            )
-            .execute()
-            .unwrap();
-        if unum.units.len()>0{
-            return Err("incompatible target unit".to_string());
-        }
-        let unit_number = format!(
-            "{:.5}",
-            unum
-            .num
-        )
-        .trim_end_matches('0')
-        .trim_end_matches('.')
-        .to_string();
-    let mut uname = u.plural;
-    if unit_number == "1".to_string() {
-            uname = u.name;
-        }
-        if exponent == 1 {
-            Ok(format!("{} {}", unit_number, uname))
-        } else {
-            Ok(format!(
-                "{} {}{}",
-                unit_number,
-                uname,
-                superscript(exponent.to_string())
-            ))
-        }
-    }else if let Some(tu)=tu{
-        let unum=UnitCalculation::Div(
-                Box::new(UnitCalculation::Number(un.clone())),
-                Box::new(UnitCalculation::Number(tu.0.clone()))
-            )
-            .execute()
-            .unwrap();
-        if unum.units.len()>0{
-            return Err("incompatible target unit".to_string());
-        }
-        let unit_number = format!(
-            "{:.5}",
-            unum
-            .num
-        )
-        .trim_end_matches('0')
-        .trim_end_matches('.')
-        .to_string();
-        Ok(format!("{unit_number} {}",tu.1))
+    if let Some(e) = conversion_error
+        && !converted
+    {
+        return Err(e);
+    }
+    if results.is_empty() {
+        return Err(error.unwrap_or("lexing failed!".to_string()));
+    }
+    for r in runners_up {
+        if !results.iter().any(|o| o.text == r.text) {
+            results.push(r);
+        }
+    }
+    Ok(results)
+}
+/// a column or row pasted from a spreadsheet, one value per line or cell, as a list literal;
+/// values on one line separated by spaces stay a calculation, like `2 3`
+pub fn parse_pasted_list(input: &str) -> Option<String> {
+    let input = input.trim();
+    if !input.contains(['\n', '\t']) {
+        return None;
+    }
+    let items = input
+        .split(['\n', '\t'])
+        .map(str::trim)
+        .filter(|i| !i.is_empty())
+        .map(strip_grouping)
+        .collect::<Vec<String>>();
+    // any other comma, like a decimal comma, would silently split a value in two
+    if items.iter().any(|i| i.contains(',')) {
+        return None;
+    }
+    (items.len() >= 2).then(|| format!("[{}]", items.join(", ")))
+}
+/// `1,234.5 m` as spreadsheets copy it, without the group separators that would split the list
+fn strip_grouping(item: &str) -> String {
+    let re = Regex::new(r"^[+-]?\d{1,3}(?:,\d{3})+(?:\.\d+)?(?:$|[^\d,])").unwrap();
+    match re.find(item) {
+        Some(m) => format!("{}{}", m.as_str().replace(',', ""), &item[m.end()..]),
+        None => item.to_string(),
+    }
+}
+/// `now in UTC+9` or `2026-12-24T18:00 in Asia/Tokyo`
+fn split_time_zone_switch(input: &str) -> Option<(String, TimeZone)> {
+    let re = Regex::new(r"^(.*?)\s+(?:in|to|as|=>|->)\s+(\S+)\s*$").unwrap();
+    let captures = re.captures(input)?;
+    Some((captures[1].to_string(), parse_time_zone(&captures[2])?))
+}
+fn evaluate_str(input: String, units: &UnitRegistry, env: &Environment) -> Result<Value, String> {
+    let tokens = lex(input, units, env).ok_or("lexing failed!".to_string())?;
+    Ok(parse_unit_conversion(tokens)?.execute()?.0)
+}
+fn datetime_result(d: Zoned) -> CalcResult {
+    CalcResult {
+        text: format_datetime(&d),
+        value: None,
+        alternatives: datetime_alternatives(&d),
+        ..Default::default()
+    }
+}
+/// the result of one reading, followed by the same result in other units
+fn execute_tokens(
+    tokens: &[Token],
+    ast: UnitConversion,
+    units: &[Unit],
+    options: CalcOptions,
+    format: &NumberFormat,
+) -> Result<Vec<CalcResult>, String> {
+    let input_units = tokens
+        .iter()
+        .filter_map(|t| match t {
+            Token::Unit(_, Some(u)) => Some(u.as_ref().clone()),
+            _ => None,
+        })
+        .collect::<Vec<Unit>>();
+    let programmer_syntax = tokens.iter().any(|t| match t {
+        Token::Number(n) => parse_radix_literal(n).is_some(),
+        Token::Function(f) => f == "~",
+        t => [Token::BitAnd, Token::BitOr, Token::BitXor, Token::ShiftLeft, Token::ShiftRight]
+            .contains(t),
+    });
+    let interpretation = ast.to_string();
+    let steps = calculation_steps(&ast, format);
+    let target = match &ast {
+        UnitConversion::Primitive(_, u) => Some(u.si.clone()),
+        UnitConversion::Complex(_, b) => b.execute().ok(),
+        _ => None,
+    };
+    let results = format_result(&ast, units, &input_units, programmer_syntax, options, format)?;
+    Ok(results
+        .into_iter()
+        .map(|r| {
+            // `10 s in Hz` shows the reciprocal, which must not look like a plain conversion
+            let inverted = target.as_ref().zip(r.value.as_ref()).is_some_and(|(t, v)| is_inverted(v, t));
+            CalcResult {
+                interpretation: if inverted { ast.to_inverted_string() } else { interpretation.clone() },
+                steps: steps.clone(),
+                ..r
+            }
+        })
+        .collect())
+}
+/// the sub-expressions of a query with their values in SI units
+fn calculation_steps(ast: &UnitConversion, format: &NumberFormat) -> Vec<(String, String)> {
+    let mut steps: Vec<(String, String)> = Vec::new();
+    for (expression, value) in ast.calculations().iter().flat_map(|c| c.steps()) {
+        let value = match value {
+            Ok(Value::Number(n)) => n.to_string_with(format.format(n.num)),
+            Ok(v) => v.to_string(),
+            Err(e) => format!("error: {e}"),
+        };
+        if value != expression && !steps.iter().any(|(e, _)| *e == expression) {
+            steps.push((expression, value));
+        }
+    }
+    steps
+}
+fn format_result(
+    ast: &UnitConversion,
+    units: &[Unit],
+    input_units: &[Unit],
+    programmer_syntax: bool,
+    options: CalcOptions,
+    format: &NumberFormat,
+) -> Result<Vec<CalcResult>, String> {
+    let (value, u, tu) = ast.execute()?;
+    let un = match value {
+        Value::Number(n) => n,
+        Value::DateTime(d) => return Ok(vec![datetime_result(d)]),
+        Value::List(l) => return format_list_result(&l, units, input_units, options, format),
+        v => {
+            return Ok(vec![CalcResult {
+                text: v.to_string(),
+                ..Default::default()
+            }]);
+        }
+    };
+    if let Some(mode) = options.integer {
+        // a width wraps every intermediate result, not just the last one
+        let i = match (ast, mode.width) {
+            (UnitConversion::Calculation(c), Some(width)) => execute_wrapped(c, width)?,
+            _ => un.to_integer()?,
+        };
+        return Ok(vec![format_integer_result(i, mode)]);
+    }
+    if programmer_syntax
+        && let Ok(i) = un.to_integer()
+    {
+        return Ok(vec![format_integer_result(i, IntegerMode::default())]);
+    }
+    if let UnitConversion::MixedUnits(_, targets, clock) = ast
+        && tu.is_none()
+    {
+        return Ok(vec![format_mixed_result(&un, targets, *clock, options.exact, format)?]);
+    }
+    // every solution in the most readable unit, like `x = 4 hours`
+    if let UnitConversion::Equation(a, b) = ast {
+        let (name, solutions) = solve(a, b)?;
+        let mut results = Vec::new();
+        for solution in solutions {
+            let rs = format_number_result(solution, None, None, units, input_units, options, format)?;
+            if let Some(r) = rs.into_iter().next() {
+                results.push(CalcResult { text: format!("{name} = {}", r.text), ..r });
+            }
+        }
+        return Ok(results);
+    }
+    format_number_result(un, u, tu, units, input_units, options, format)
+}
+/// every aggregate of a list as its own row, like `mean = 2.9 meters`
+fn format_list_result(
+    list: &[UnitNumber],
+    units: &[Unit],
+    input_units: &[Unit],
+    options: CalcOptions,
+    format: &NumberFormat,
+) -> Result<Vec<CalcResult>, String> {
+    let mut results = Vec::new();
+    let mut error = None;
+    for f in AGGREGATES {
+        // e.g. the standard deviation of a single value, which leaves the other aggregates
+        let value = match aggregate(f, list) {
+            Ok(value) => value,
+            Err(e) => {
+                error.get_or_insert(e);
+                continue;
+            }
+        };
+        let rs = format_number_result(value, None, None, units, input_units, options, format)?;
+        if let Some(r) = rs.into_iter().next() {
+            results.push(CalcResult { text: format!("{f} = {}", r.text), ..r });
+        }
+    }
+    match error {
+        Some(e) if results.is_empty() => Err(e),
+        _ => Ok(results),
+    }
+}
+/// a number in the target unit, or else in the most readable units followed by the runners-up
+fn format_number_result(
+    un: UnitNumber,
+    u: Option<Unit>,
+    tu: Option<(UnitNumber, String)>,
+    units: &[Unit],
+    input_units: &[Unit],
+    options: CalcOptions,
+    format: &NumberFormat,
+) -> Result<Vec<CalcResult>, String> {
+    let displays = if options.all_units {
+        all_units_display(&un, units)?
+    } else {
+        choose_display(&un, u, tu, units, input_units)?
+    };
+    Ok(displays
+        .into_iter()
+        .map(|display| {
+            let text = display.format(format, options.exact);
+            let mut alternatives = Vec::new();
+            if !options.exact {
+                for notation in [Notation::Scientific, Notation::Engineering, Notation::Full] {
+                    let alternative = display.format(&format.with_notation(notation), false);
+                    if alternative != text && !alternatives.contains(&alternative) {
+                        alternatives.push(alternative);
+                    }
+                }
+            }
+            CalcResult {
+                copy_forms: display.copy_forms(&un, format, options.exact),
+                text,
+                value: Some(un.clone()),
+                alternatives,
+                ..Default::default()
+            }
+        })
+        .collect())
+}
+/// splits a value over several units, e.g. `5 feet 3.6 inches` or `1:02:05` for a clock
+fn format_mixed_result(
+    un: &UnitNumber,
+    targets: &[Unit],
+    clock: bool,
+    exact: bool,
+    format: &NumberFormat,
+) -> Result<CalcResult, String> {
+    if targets.iter().any(|u| !un.addable(u.si.cleaned())) {
+        return Err("incompatible target unit".to_string());
+    }
+    // the largest unit takes the whole part, the smallest the rest
+    let mut targets = targets.to_vec();
+    targets.sort_by(|a, b| b.si.num.total_cmp(&a.si.num));
+    let (last, whole) = targets.split_last().ok_or("no target units".to_string())?;
+    let negative = un.num < 0.0;
+    let mut rest = if negative { -un.clone() } else { un.clone() };
+    let mut parts = Vec::new();
+    for u in whole {
+        let n = rest.clone() / u.si.cleaned();
+        let count = match &n.exact {
+            Some(e) => e.floor().to_integer(),
+            // against rounding errors like 2.9999999999 feet
+            None => BigInt::from_f64((n.num + 1e-9).floor()).ok_or("the value is not finite!".to_string())?,
+        };
+        rest = (rest - u.si.cleaned().scaled(&BigRational::from_integer(count.clone())))?;
+        parts.push((count.to_string(), u));
+    }
+    let mut last_num = rest / last.si.cleaned();
+    if last_num.num < 0.0 {
+        last_num = UnitNumber::from_integer(BigInt::zero());
+    }
+    parts.push((format_num(&last_num, exact, format), last));
+    let text = if clock {
+        parts
+            .iter()
+            .enumerate()
+            .map(|(i, (n, _))| match n.find('.').unwrap_or(n.len()) {
+                1 if i > 0 => format!("0{n}"),
+                _ => n.clone(),
+            })
+            .collect::<Vec<String>>()
+            .join(":")
     } else {
-        Ok(un.to_string())
+        let shown = parts.iter().filter(|(n, _)| n != "0").collect::<Vec<_>>();
+        let shown = if shown.is_empty() { vec![&parts[parts.len() - 1]] } else { shown };
+        shown
+            .iter()
+            .map(|(n, u)| format!("{n} {}", if n == "1" { &u.name } else { &u.plural }))
+            .collect::<Vec<String>>()
+            .join(" ")
+    };
+    Ok(CalcResult {
+        text: if negative { format!("-{text}") } else { text },
+        value: Some(un.clone()),
+        ..Default::default()
+    })
+}
+/// every unit `un` can be shown in, leaving out prefixed variants
+fn all_units_display(un: &UnitNumber, units: &[Unit]) -> Result<Vec<DisplayNumber>, String> {
+    let displays = units
+        .iter()
+        // the speed of light is not a unit to show a speed in
+        .filter(|u| u.name == u.base_name && u.priority > NEVER_CHOSEN && un.addable(u.si.cleaned()))
+        .map(|u| DisplayNumber {
+            num: un.clone() / u.si.cleaned(),
+            unit: DisplayUnit::Units(vec![(u.clone(), Rational64::from_integer(1))]),
+        })
+        .collect::<Vec<DisplayNumber>>();
+    if displays.is_empty() {
+        return Err("no unit fits this value".to_string());
+    }
+    Ok(displays)
+}
+/// a unit symbol like `kΩ` or `m/s²` in plain ASCII for siunitx, e.g. `k\ohm` or `m/s^{2}`
+fn latex_symbol(symbol: &str) -> String {
+    let mut latex = String::new();
+    let mut exponent = String::new();
+    for c in symbol.chars() {
+        if let Some(i) = "⁰¹²³⁴⁵⁶⁷⁸⁹⁻".chars().position(|s| s == c) {
+            exponent.push("0123456789-".as_bytes()[i] as char);
+            continue;
+        }
+        if !exponent.is_empty() {
+            latex += &format!("^{{{exponent}}}");
+            exponent.clear();
+        }
+        match c {
+            'Ω' => latex += "\\ohm",
+            'µ' | 'μ' => latex += "\\micro ",
+            '°' => latex += "\\degree",
+            '′' => latex += "\\arcminute",
+            '″' => latex += "\\arcsecond",
+            'Å' => latex += "\\angstrom",
+            '%' => latex += "\\percent",
+            'π' => latex += "\\pi",
+            c => latex.push(c),
+        }
+    }
+    if !exponent.is_empty() {
+        latex += &format!("^{{{exponent}}}");
+    }
+    latex
+}
+/// shows an integer in the requested base, with all other bases as alternatives
+fn format_integer_result(mut i: BigInt, mode: IntegerMode) -> CalcResult {
+    if let Some(width) = mode.width {
+        i = wrap(&i, width);
+    }
+    let base = mode.base.unwrap_or(Base::Decimal);
+    CalcResult {
+        text: format_integer(&i, base, mode.width),
+        value: Some(UnitNumber::from_integer(i.clone())),
+        alternatives: Base::ALL
+            .iter()
+            .filter(|b| **b != base)
+            .map(|b| format_integer(&i, *b, mode.width))
+            .collect(),
+        ..Default::default()
+    }
+}
+/// units with a root written as a fraction, like `V/√Hz` rather than `V Hz^(-1/2)`
+fn root_symbols(factors: &[(Unit, Rational64)]) -> String {
+    let join = |negative: bool| {
+        factors
+            .iter()
+            .filter(|(_, e)| e.is_negative() == negative)
+            .map(|(u, e)| with_exponent(u.abbreviation.clone(), e.abs()))
+            .collect::<Vec<String>>()
+            .join(" ")
+    };
+    match (join(false), join(true)) {
+        (numerator, denominator) if denominator.is_empty() => numerator,
+        (numerator, denominator) if numerator.is_empty() => format!("1/{denominator}"),
+        (numerator, denominator) => format!("{numerator}/{denominator}"),
+    }
+}
+/// a number result divided by the unit it is shown in
+pub struct DisplayNumber {
+    pub num: UnitNumber,
+    pub unit: DisplayUnit,
+}
+pub enum DisplayUnit {
+    /// units from the list with their exponents, e.g. square meters or kilowatt hours
+    Units(Vec<(Unit, Rational64)>),
+    /// the text of a conversion target like `km/h`
+    Target(String),
+    /// the SI base units still left in the number
+    Base,
+}
+impl DisplayNumber {
+    pub fn format(&self, format: &NumberFormat, exact: bool) -> String {
+        let n = format_num(&self.num, exact, format);
+        match &self.unit {
+            // roots read best in symbols, like `V/√Hz`
+            DisplayUnit::Units(factors) if factors.iter().any(|(_, e)| !e.is_integer()) => {
+                format!("{n} {}", self.symbols(false))
+            }
+            DisplayUnit::Units(factors) => {
+                format!("{n} {}", describe(factors, n == "1" || self.num.num == 1.0))
+            }
+            DisplayUnit::Target(t) if t.starts_with("min/") && self.num.num.is_finite() && !exact => {
+                format!("{} {t}", format_pace(self.num.num))
+            }
+            DisplayUnit::Target(t) => format!("{n} {t}"),
+            DisplayUnit::Base => self.num.to_string_with(n),
+        }
+    }
+    /// the unit symbols, in siunitx notation for LaTeX
+    fn symbols(&self, latex: bool) -> String {
+        let symbol = |name: String, exp: Rational64| match latex {
+            true if exp == Rational64::from_integer(1) => latex_symbol(&name),
+            true => format!("{}^{{{exp}}}", latex_symbol(&name)),
+            false => with_exponent(name, exp),
+        };
+        let symbols = match &self.unit {
+            DisplayUnit::Units(factors) if !latex && factors.iter().any(|(_, e)| !e.is_integer()) => {
+                vec![root_symbols(factors)]
+            }
+            DisplayUnit::Units(factors) => factors.iter().map(|(u, exp)| symbol(u.abbreviation.clone(), *exp)).collect(),
+            DisplayUnit::Target(t) if latex => t.split(' ').map(latex_symbol).collect(),
+            DisplayUnit::Target(t) => vec![t.clone()],
+            DisplayUnit::Base => match latex {
+                true => self.num.cleaned().units.iter().map(|e| symbol(e.unit.to_string(), e.exp)).collect(),
+                false => self.num.cleaned().units.iter().map(unit_exp_to_superscript_exp).collect(),
+            },
+        };
+        // siunitx reads `.` as a product of units
+        symbols.join(if latex { "." } else { " " })
+    }
+    /// the representations offered for copying, each with a label
+    fn copy_forms(&self, un: &UnitNumber, format: &NumberFormat, exact: bool) -> Vec<(String, String)> {
+        let n = format_num(&self.num, exact, format);
+        let full = format_full_precision(&self.num);
+        let symbols = self.symbols(false);
+        let (base_units, _) = signature(un);
+        let base = format_full_precision(&in_kilograms(un));
+        let mut forms = vec![("number".to_string(), n.clone())];
+        if !symbols.is_empty() {
+            forms.push(("symbols".to_string(), format!("{n} {symbols}")));
+            forms.push(("LaTeX".to_string(), format!("\\qty{{{full}}}{{{}}}", self.symbols(true))));
+            forms.push(("full precision".to_string(), format!("{full} {symbols}")));
+        } else {
+            forms.push(("LaTeX".to_string(), format!("\\num{{{full}}}")));
+            forms.push(("full precision".to_string(), full));
+        }
+        if !base_units.is_empty() {
+            forms.push(("SI base units".to_string(), format!("{base} {base_units}")));
+        }
+        // e.g. the number and full precision of an integer
+        let mut seen = Vec::new();
+        forms.retain(|(_, form)| match seen.contains(form) {
+            true => false,
+            false => {
+                seen.push(form.clone());
+                true
+            }
+        });
+        forms
+    }
+}
+/// `un` as a number of `target`, or its reciprocal for inverse dimensions like mpg and L/100km or
+/// speed and pace
+fn in_target_unit(un: &UnitNumber, target: &UnitNumber) -> Result<UnitNumber, String> {
+    if !is_inverted(un, target) {
+        return Ok(un.clone() / target.clone());
+    }
+    let reciprocal = UnitNumber::from_integer(BigInt::from(1)) / un.clone() / target.clone();
+    if reciprocal.units.is_empty() {
+        return Ok(reciprocal);
+    }
+    Err("incompatible target unit".to_string())
+}
+/// whether `un` only fits `target` as its reciprocal
+fn is_inverted(un: &UnitNumber, target: &UnitNumber) -> bool {
+    !(un.clone() / target.clone()).units.is_empty()
+}
+/// minutes as `m:ss`, the way paces like `5:30 min/km` are written
+fn format_pace(minutes: f64) -> String {
+    let seconds = (minutes.abs() * 60.0).round() as u64;
+    let sign = if minutes < 0.0 { "-" } else { "" };
+    format!("{sign}{}:{:02}", seconds / 60, seconds % 60)
+}
+/// picks the units a result is shown in, unless the query asked for some; the runners-up follow
+fn choose_display(
+    un: &UnitNumber,
+    u: Option<Unit>,
+    tu: Option<(UnitNumber, String)>,
+    units: &[Unit],
+    input_units: &[Unit],
+) -> Result<Vec<DisplayNumber>, String> {
+    if let Some(u) = u {
+        return Ok(vec![DisplayNumber {
+            num: in_target_unit(un, &u.si)?,
+            unit: DisplayUnit::Units(vec![(u, Rational64::from_integer(1))]),
+        }]);
+    }
+    if let Some(tu) = tu {
+        return Ok(vec![DisplayNumber { num: in_target_unit(un, &tu.0)?, unit: DisplayUnit::Target(tu.1) }]);
+    }
+    let simplifications = simplify(un, units, input_units);
+    let Some(best) = simplifications.first() else {
+        // angles are plain numbers in SI, but `90 deg` still reads best in degrees
+        if let Some(angle) = input_units.iter().find(|u| u.si.units.is_empty() && u.priority > NEVER_CHOSEN)
+            && un.units.is_empty()
+        {
+            return Ok(vec![DisplayNumber {
+                num: un.clone() / angle.si.cleaned(),
+                unit: DisplayUnit::Units(vec![(angle.clone(), Rational64::from_integer(1))]),
+            }]);
+        }
+        return Ok(vec![DisplayNumber { num: un.clone(), unit: DisplayUnit::Base }]);
+    };
+    // other units rather than other prefixes of the same ones
+    let base_names = |s: &Simplification| {
+        let mut names = s.factors.iter().map(|(u, e)| (u.base_name.clone(), *e)).collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+    // the same number in as many units is just another name, like square meters and meters²
+    let same_name = |a: &Simplification, b: &Simplification| {
+        a.factors.len() == b.factors.len() && a.num.num == b.num.num
+    };
+    let mut chosen: Vec<&Simplification> = Vec::new();
+    for s in &simplifications {
+        if s.score < best.score - MAX_RUNNER_UP_DISTANCE || chosen.len() >= MAX_RUNNERS_UP {
+            break;
+        }
+        if !chosen.iter().any(|c| base_names(c) == base_names(s) || same_name(c, s)) {
+            chosen.push(s);
+        }
+    }
+    Ok(chosen
+        .into_iter()
+        .map(|s| DisplayNumber { num: s.num.clone(), unit: DisplayUnit::Units(s.factors.clone()) })
+        .collect())
+}
+/// applies the number format, or shows the exact value in exact mode
+fn format_num(n: &UnitNumber, exact: bool, format: &NumberFormat) -> String {
+    match (&n.exact, exact) {
+        (Some(e), true) => to_exact_string(e),
+        (None, true) => format!("≈ {}", format.format(n.num)),
+        _ if format.notation == Notation::Full => format_full_precision(n),
+        _ => format.format(n.num),
+    }
+}
+/// every digit of an exact decimal, or of the float otherwise
+fn format_full_precision(n: &UnitNumber) -> String {
+    n.exact
+        .as_ref()
+        .and_then(to_terminating_decimal)
+        .filter(|d| d.len() <= 40)
+        .unwrap_or_else(|| format_full(n.num))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the text of every result row
+    fn texts(query: &str) -> Result<Vec<String>, String> {
+        texts_in(query, Notation::Auto)
+    }
+    fn texts_in(query: &str, notation: Notation) -> Result<Vec<String>, String> {
+        let format = NumberFormat { notation, decimal_separator: '.', group_separator: Some(','), ..Default::default() };
+        let results = execute_unit_str(query.to_string(), &get_units(), &Environment::default(), CalcOptions::default(), &format)?;
+        Ok(results.into_iter().map(|r| r.text).collect())
+    }
+
+    #[test]
+    fn square_root_units() {
+        assert_eq!(texts("1 V / sqrt(1 Hz)"), Ok(vec!["1 V/√Hz".to_string()]));
+        assert_eq!(texts("3 nV / sqrt(1 Hz)"), Ok(vec!["3 nV/√Hz".to_string()]));
+    }
+
+    #[test]
+    fn aggregates_of_one_value() {
+        assert_eq!(
+            texts("[3 m]"),
+            Ok(vec![
+                "sum = 3 meters".to_string(),
+                "mean = 3 meters".to_string(),
+                "median = 3 meters".to_string(),
+                "min = 3 meters".to_string(),
+                "max = 3 meters".to_string(),
+            ])
+        );
+        assert_eq!(texts("[]"), Err("the list is empty".to_string()));
+    }
+
+    #[test]
+    fn constants_next_to_units() {
+        assert_eq!(texts("1 Gbit/s * 1 h"), Ok(vec!["3.6 terabits".to_string()]));
+        assert_eq!(texts("1 GB"), Ok(vec!["1 gigabyte".to_string(), "8 gigabits".to_string()]));
+        assert_eq!(texts("h * 500 THz in eV"), Ok(vec!["2.06783 electronvolts".to_string()]));
+    }
+
+    #[test]
+    fn runners_up() {
+        assert_eq!(texts("1 Bq"), Ok(vec!["1 becquerel".to_string()]));
+        assert_eq!(texts("1 Sv"), Ok(vec!["1 sievert".to_string()]));
+        assert_eq!(texts("1 s^-1"), Ok(vec!["1 hertz".to_string()]));
+        assert_eq!(texts("1 kg m / s^2"), Ok(vec!["1 newton".to_string()]));
+        assert_eq!(texts("8 bit"), Ok(vec!["8 bits".to_string(), "1 byte".to_string()]));
+        assert_eq!(texts("5 kW h"), Ok(vec!["5 kilowatt hours".to_string(), "18 megajoules".to_string()]));
+    }
+
+    #[test]
+    fn full_notation() {
+        let first = |query| texts_in(query, Notation::Full).unwrap().remove(0);
+        assert_eq!(first("0.1 + 0.2"), "0.3");
+        assert_eq!(first("1e-9 m"), "1 nanometer");
+        assert_eq!(first("3 in"), "3 inches");
+        assert_eq!(first("1/3"), "0.333333333333333");
+        assert_eq!(first("2^0.5"), "1.4142135623731");
     }
 }
@@ -3,4 +3,17 @@ pub mod lexer;
 pub mod unit_number_parser;
 pub mod parser;
 pub mod interpreter;
-pub mod unit_calculator;
\ No newline at end of file
+pub mod unit_calculator;
+pub mod value;
+pub mod environment;
+pub mod unit_definitions;
+pub mod exact;
+pub mod number_format;
+pub mod programmer;
+pub mod datetime;
+pub mod unit_registry;
+pub mod simplify;
+pub mod dimensions;
+pub mod solver;
+pub mod statistics;
+pub mod history;
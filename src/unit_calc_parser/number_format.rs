@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+
+const NUMBER_FORMAT_FILE: &str = "number_format.json";
+/// digits a float holds reliably, so `0.1 + 0.2` shows as 0.3 rather than 0.30000000000000004
+const FULL_SIGNIFICANT_FIGURES: usize = 15;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Notation {
+    /// plain digits, switching to `exponent_notation` for very large or small magnitudes
+    Auto,
+    Fixed,
+    Scientific,
+    Engineering,
+    /// every digit of the exact value, or of the float up to the digits it holds reliably, without
+    /// grouping, as the calculator itself reads numbers
+    Full,
+}
+
+/// how calculator results are printed, read from `number_format.json` if it exists
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NumberFormat {
+    pub significant_figures: usize,
+    pub notation: Notation,
+    /// used by `Auto` outside of `min_fixed_exponent..=max_fixed_exponent`
+    pub exponent_notation: Notation,
+    pub min_fixed_exponent: i32,
+    pub max_fixed_exponent: i32,
+    pub decimal_separator: char,
+    /// groups the digits of integer parts longer than four digits
+    pub group_separator: Option<char>,
+}
+impl Default for NumberFormat {
+    fn default() -> Self {
+        let (decimal_separator, group_separator) = locale_separators();
+        Self {
+            significant_figures: 6,
+            notation: Notation::Auto,
+            exponent_notation: Notation::Scientific,
+            min_fixed_exponent: -5,
+            max_fixed_exponent: 11,
+            decimal_separator,
+            group_separator: Some(group_separator),
+        }
+    }
+}
+impl NumberFormat {
+    pub fn load() -> Self {
+        match std::fs::read_to_string(NUMBER_FORMAT_FILE) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                eprintln!("could not parse {NUMBER_FORMAT_FILE}: {e}");
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+    pub fn with_notation(&self, notation: Notation) -> Self {
+        Self {
+            notation,
+            ..self.clone()
+        }
+    }
+    pub fn format(&self, num: f64) -> String {
+        if !num.is_finite() || self.notation == Notation::Full {
+            return format_full(num);
+        }
+        if num == 0.0 {
+            return "0".to_string();
+        }
+        let exponent = num.abs().log10().floor() as i32;
+        let notation = match self.notation {
+            Notation::Auto
+                if exponent < self.min_fixed_exponent || exponent > self.max_fixed_exponent =>
+            {
+                self.exponent_notation
+            }
+            Notation::Auto => Notation::Fixed,
+            n => n,
+        };
+        match notation {
+            Notation::Scientific => self.format_with_exponent(num, exponent, 1),
+            Notation::Engineering => {
+                self.format_with_exponent(num, exponent.div_euclid(3) * 3, 3)
+            }
+            _ => {
+                let decimals = (self.significant_figures as i32 - 1 - exponent).max(0) as usize;
+                self.localize(&format!("{:.*}", decimals, num))
+            }
+        }
+    }
+    /// `step` is 1 for scientific and 3 for engineering notation
+    fn format_with_exponent(&self, num: f64, exponent: i32, step: i32) -> String {
+        let mantissa = num / 10f64.powi(exponent);
+        let digits = mantissa.abs().log10().floor() as i32;
+        let decimals = (self.significant_figures as i32 - 1 - digits).max(0) as usize;
+        let rounded = format!("{:.*}", decimals, mantissa);
+        // rounding can carry over, e.g. 9.9999 becoming 10.000
+        if rounded.parse::<f64>().is_ok_and(|m| m.abs() >= 10f64.powi(step)) {
+            return self.format_with_exponent(num, exponent + step, step);
+        }
+        if exponent == 0 {
+            return self.localize(&rounded);
+        }
+        format!("{}e{}", self.localize(&rounded), exponent)
+    }
+    /// trims trailing zeros and applies the separators
+    fn localize(&self, s: &str) -> String {
+        let s = if s.contains('.') {
+            s.trim_end_matches('0').trim_end_matches('.')
+        } else {
+            s
+        };
+        let (sign, s) = match s.strip_prefix('-') {
+            Some(s) => ("-", s),
+            None => ("", s),
+        };
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        let mut grouped = String::new();
+        for (i, c) in int.chars().enumerate() {
+            if let Some(g) = self.group_separator
+                && int.len() > 4
+                && i > 0
+                && (int.len() - i) % 3 == 0
+            {
+                grouped.push(g);
+            }
+            grouped.push(c);
+        }
+        if frac.is_empty() {
+            format!("{sign}{grouped}")
+        } else {
+            format!("{sign}{grouped}{}{frac}", self.decimal_separator)
+        }
+    }
+}
+/// the shortest digits that read back as `num` once rounded to `FULL_SIGNIFICANT_FIGURES`
+pub fn format_full(num: f64) -> String {
+    let rounded = format!("{:.*e}", FULL_SIGNIFICANT_FIGURES - 1, num);
+    format!("{}", rounded.parse::<f64>().unwrap_or(num))
+}
+/// decimal and group separator for the language in `LC_ALL`, `LC_NUMERIC` or `LANG`
+fn locale_separators() -> (char, char) {
+    let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .iter()
+        .filter_map(|v| std::env::var(v).ok())
+        .find(|v| !v.is_empty())
+        .unwrap_or_default();
+    match locale.get(..2).unwrap_or("") {
+        "de" | "nl" | "it" | "es" | "pt" | "da" | "id" | "tr" => (',', '.'),
+        "fr" | "ru" | "pl" | "sv" | "fi" | "nb" | "cs" | "uk" => (',', ' '),
+        _ => ('.', ','),
+    }
+}
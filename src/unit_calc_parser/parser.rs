@@ -1,3 +1,8 @@
+use std::fmt;
+
+use jiff::Zoned;
+use num_bigint::BigInt;
+
 use crate::unit_calc_parser::{
     lexer::{Token, Unit},
     unit_number_parser::{superscript, UnitNumber},
@@ -10,70 +15,416 @@ pub enum UnitCalculation {
     Div(Box<UnitCalculation>, Box<UnitCalculation>),
     Pow(Box<UnitCalculation>, Box<UnitCalculation>),
     Bracket(Box<UnitCalculation>),
+    Function(String, Box<UnitCalculation>),
     Number(UnitNumber),
+    /// the unknown of an equation
+    Unknown(String),
+    /// a unit from the list, kept so the interpretation can show which one was meant
+    Unit(Box<Unit>),
+    DateTime(Zoned),
+    /// `[3 m, 250 cm, 4 ft]`
+    List(Vec<UnitCalculation>),
+    Compare(Box<UnitCalculation>, Vec<(Token, UnitCalculation)>),
+    And(Box<UnitCalculation>, Box<UnitCalculation>),
+    Or(Box<UnitCalculation>, Box<UnitCalculation>),
+    At(Box<UnitCalculation>, Box<UnitCalculation>),
+    BitAnd(Box<UnitCalculation>, Box<UnitCalculation>),
+    BitOr(Box<UnitCalculation>, Box<UnitCalculation>),
+    BitXor(Box<UnitCalculation>, Box<UnitCalculation>),
+    ShiftLeft(Box<UnitCalculation>, Box<UnitCalculation>),
+    ShiftRight(Box<UnitCalculation>, Box<UnitCalculation>),
+    /// `a + p%`, `a` increased by a percentage of itself
+    AddPercent(Box<UnitCalculation>, Box<UnitCalculation>),
+    /// `a - p%`, `a` decreased by a percentage of itself
+    SubtractPercent(Box<UnitCalculation>, Box<UnitCalculation>),
+    /// `p% of a`
+    PercentOf(Box<UnitCalculation>, Box<UnitCalculation>),
+    /// `a is what % of b`, the share `a` is of `b`
+    PercentRatio(Box<UnitCalculation>, Box<UnitCalculation>),
+    /// `a is what % more than b`, or less than `b` if `true`
+    PercentDifference(Box<UnitCalculation>, Box<UnitCalculation>, bool),
 }
 pub enum UnitConversion {
-    ComplexUnitConversion(UnitCalculation, UnitCalculation),
-    PrimitiveUnitConversion(UnitCalculation, Unit),
+    Complex(UnitCalculation, UnitCalculation),
+    Primitive(UnitCalculation, Box<Unit>),
+    /// a value split into several units like `ft and in`, or a clock like `h:min:s` if `true`
+    MixedUnits(UnitCalculation, Vec<Unit>, bool),
+    /// `2x + 5 m = 11 m`, solved for the unknown
+    Equation(UnitCalculation, UnitCalculation),
     Calculation(UnitCalculation),
 }
-impl ToString for UnitConversion{
-    fn to_string(&self) -> String {
+impl fmt::Display for UnitConversion {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::Calculation(c)=>{c.to_string()}
+            Self::Primitive(c,u)=>{format!("{} → {}",c,u.name)}
+            Self::Complex(c,u)=>{format!("{} → {}",c,u)}
+            Self::MixedUnits(c, units, clock) => {
+                let separator = if *clock { ":" } else { " " };
+                let units = units.iter().map(|u| u.abbreviation.clone()).collect::<Vec<String>>();
+                format!("{} → {}", c, units.join(separator))
+            }
+            Self::Equation(a, b) => { format!("{} = {}", a, b) }
+        };
+        formatter.write_str(&s)
+    }
+}
+/// `²` or `⁻¹` for whole exponents, `^(1/2)` otherwise
+fn exponent(b: &UnitCalculation) -> String {
+    if let UnitCalculation::Number(b)=b && let Ok(b)=b.to_i64(){
+        return superscript(b.to_string())
+    }
+    if let UnitCalculation::Function(f, b)=b && f=="-" && let UnitCalculation::Number(b)=b.as_ref() && let Ok(b)=b.to_i64(){
+        return superscript((-b).to_string())
+    }
+    format!("^{}", b)
+}
+impl fmt::Display for UnitCalculation {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::Plus(a, b)=>{format!("{} + {}", a, b)}
+            Self::Minus(a, b) => { format!("{} - {}", a, b) }
+            Self::Mult(a, b) => { format!("{} * {}", a, b) }
+            Self::ImplMult(a, b) => { format!("{} {}", a, b) }
+            Self::Div(a, b) => { format!("{}/{}", a, b) }
+            Self::Pow(a, b) => { format!("{}{}", a, exponent(b)) }
+            Self::Bracket(a) => { format!("({})", a) }
+            // a space keeps a name like `unix` apart from its argument, `-` and `√` need none
+            Self::Function(f, a) if f.starts_with(char::is_alphabetic) && !matches!(a.as_ref(), Self::Bracket(_)) => {
+                format!("{} {}", f, a)
+            }
+            Self::Function(f, a) => { format!("{}{}", f, a) }
+            Self::Number(n) => { n.to_string() }
+            Self::Unknown(name) => { name.clone() }
+            // the name rather than the abbreviation, which may be shared by several units
+            Self::Unit(u) => { u.name.clone() }
+            Self::DateTime(d) => { d.strftime("%Y-%m-%dT%H:%M:%S").to_string() }
+            Self::List(items) => {
+                format!("[{}]", items.iter().map(|i| i.to_string()).collect::<Vec<String>>().join(", "))
+            }
+            Self::Compare(a, c) => {
+                let mut s = a.to_string();
+                for (t, b) in c {
+                    s += &format!(" {} {}", t, b);
+                }
+                s
+            }
+            Self::And(a, b) => { format!("{} and {}", a, b) }
+            Self::Or(a, b) => { format!("{} or {}", a, b) }
+            Self::At(a, b) => { format!("{} at {}", a, b) }
+            Self::BitAnd(a, b) => { format!("{} & {}", a, b) }
+            Self::BitOr(a, b) => { format!("{} | {}", a, b) }
+            Self::BitXor(a, b) => { format!("{} xor {}", a, b) }
+            Self::ShiftLeft(a, b) => { format!("{} << {}", a, b) }
+            Self::ShiftRight(a, b) => { format!("{} >> {}", a, b) }
+            // spelled out, so it is clear which meaning of the percentage was used
+            Self::AddPercent(a, p) => { format!("{} × (1 + {})", a.grouped(), p) }
+            Self::SubtractPercent(a, p) => { format!("{} × (1 - {})", a.grouped(), p) }
+            Self::PercentOf(p, a) => { format!("{} of {}", p, a.grouped()) }
+            Self::PercentRatio(a, b) => { format!("{}/{}", a.grouped(), b.grouped()) }
+            Self::PercentDifference(a, b, false) => { format!("({} - {})/{}", a, b, b.grouped()) }
+            Self::PercentDifference(a, b, true) => { format!("({} - {})/{}", b, a, b.grouped()) }
+        };
+        formatter.write_str(&s)
+    }
+}
+impl UnitCalculation {
+    pub fn children(&self) -> Vec<&UnitCalculation> {
         match self {
-            Self::Calculation(c)=>{format!("{}",c.to_string())}
-            Self::PrimitiveUnitConversion(c,u)=>{format!("{} as {}",c.to_string(),u.plural)}
-            Self::ComplexUnitConversion(c,u)=>{format!("{} as {}",c.to_string(),u.to_string())}
+            Self::Plus(a, b)
+            | Self::Minus(a, b)
+            | Self::Mult(a, b)
+            | Self::ImplMult(a, b)
+            | Self::Div(a, b)
+            | Self::Pow(a, b)
+            | Self::And(a, b)
+            | Self::Or(a, b)
+            | Self::At(a, b)
+            | Self::BitAnd(a, b)
+            | Self::BitOr(a, b)
+            | Self::BitXor(a, b)
+            | Self::ShiftLeft(a, b)
+            | Self::ShiftRight(a, b)
+            | Self::AddPercent(a, b)
+            | Self::SubtractPercent(a, b)
+            | Self::PercentOf(a, b)
+            | Self::PercentRatio(a, b)
+            | Self::PercentDifference(a, b, _) => vec![a, b],
+            Self::Bracket(a) | Self::Function(_, a) => vec![a],
+            Self::List(items) => items.iter().collect(),
+            Self::Compare(a, c) => {
+                let mut children = vec![a.as_ref()];
+                children.extend(c.iter().map(|(_, b)| b));
+                children
+            }
+            Self::Number(_) | Self::Unknown(_) | Self::Unit(_) | Self::DateTime(_) => Vec::new(),
         }
     }
-}
-impl ToString for UnitCalculation{
-    fn to_string(&self) -> String {
+    /// the names of the unknowns, once for every time they occur
+    pub fn unknowns(&self) -> Vec<&str> {
         match self {
-            Self::Plus(a, b)=>{format!("{} + {}", a.to_string(), b.to_string())}
-            Self::Minus(a, b) => { format!("{} - {}", a.to_string(), b.to_string()) }
-            Self::Mult(a, b) => { format!("{} * {}", a.to_string(), b.to_string()) }
-            Self::ImplMult(a, b) => { format!("{}{}", a.to_string(), b.to_string()) }
-            Self::Div(a, b) => { format!("{}/{}", a.to_string(), b.to_string()) }
-            Self::Pow(a, b) => {
-                if let Self::Number(b)=b.as_ref(){
-                    return format!("{}{}", a.to_string(), superscript(b.to_string()))
-                }
-                format!("{}^{}", a.to_string(), b.to_string())
+            Self::Unknown(name) => vec![name.as_str()],
+            c => c.children().into_iter().flat_map(|c| c.unknowns()).collect(),
+        }
+    }
+    /// a conversion target like `km/h` or `m/s²`, written with unit symbols instead of names
+    pub fn symbols(&self) -> String {
+        match self {
+            Self::Unit(u) => u.abbreviation.clone(),
+            Self::Mult(a, b) => format!("{} * {}", a.symbols(), b.symbols()),
+            Self::ImplMult(a, b) => format!("{} {}", a.symbols(), b.symbols()),
+            Self::Div(a, b) => format!("{}/{}", a.symbols(), b.symbols()),
+            Self::Pow(a, b) => format!("{}{}", a.symbols(), exponent(b)),
+            Self::Bracket(a) => format!("({})", a.symbols()),
+            c => c.to_string(),
+        }
+    }
+    /// in brackets unless it is a single number or unit
+    fn grouped(&self) -> String {
+        match self {
+            Self::Number(_)
+            | Self::Unknown(_)
+            | Self::Unit(_)
+            | Self::DateTime(_)
+            | Self::Bracket(_)
+            | Self::List(_)
+            | Self::Function(..) => {
+                self.to_string()
             }
-            Self::Bracket(a) => { format!("({})", a.to_string()) }
-            Self::Number(n) => { n.to_string() }
+            _ => format!("({})", self),
+        }
+    }
+}
+impl UnitConversion {
+    /// whether the query asked for a conversion with `in`, `to`, `as`, ...
+    pub fn is_conversion(&self) -> bool {
+        !matches!(self, Self::Calculation(_) | Self::Equation(..))
+    }
+    /// the interpretation of a conversion that needed the reciprocal, like `1/(10 second) → hertz`
+    pub fn to_inverted_string(&self) -> String {
+        match self {
+            Self::Primitive(c, u) => format!("1/{} → {}", c.grouped(), u.name),
+            Self::Complex(c, u) => format!("1/{} → {}", c.grouped(), u),
+            c => c.to_string(),
+        }
+    }
+    /// the calculations in the query, including a complex conversion target
+    pub fn calculations(&self) -> Vec<&UnitCalculation> {
+        match self {
+            Self::Calculation(c)
+            | Self::Primitive(c, _)
+            | Self::MixedUnits(c, _, _) => vec![c],
+            Self::Complex(a, b) | Self::Equation(a, b) => vec![a, b],
         }
     }
 }
 pub fn parse_unit_conversion(tokens: Vec<Token>) -> Result<UnitConversion, String> {
+    check_clock_numbers(&tokens)?;
+    let tokens = mark_unary_minus(tokens);
+    if let SplitAtOut::Split(a, _, question) = split_at(tokens.clone(), vec![Token::IsWhat]) {
+        return parse_percent_question(a, question);
+    }
+    if let SplitAtOut::Split(a, _, b) = split_at(tokens.clone(), vec![Token::Equals]) {
+        return Ok(UnitConversion::Equation(parse_unit_or(a)?, parse_unit_or(b)?));
+    }
     match split_at(tokens, vec![Token::Convert]) {
         SplitAtOut::Split(eq1, _, eq2) => {
-            if let Some(Token::Unit(_, Some(u))) = eq2.first() {
-                if eq2.len()==1{
-                    return Ok(UnitConversion::PrimitiveUnitConversion(
-                        parse_unit_add_sub(eq1)?,
-                        u.clone(),
-                    ))
-                }
+            if let Some(Token::Unit(_, Some(u))) = eq2.first()
+                && eq2.len()==1
+            {
+                return Ok(UnitConversion::Primitive(
+                    parse_unit_or(eq1)?,
+                    u.clone(),
+                ))
+            }
+            if let Some((units, clock)) = parse_mixed_units(&eq2) {
+                return Ok(UnitConversion::MixedUnits(parse_unit_or(eq1)?, units, clock));
             }
-            Ok(UnitConversion::ComplexUnitConversion(parse_unit_add_sub(eq1)?, parse_unit_add_sub(eq2)?))
+            Ok(UnitConversion::Complex(parse_unit_or(eq1)?, parse_unit_add_sub(eq2)?))
         }
-        SplitAtOut::NoSplit(tokens) => Ok(UnitConversion::Calculation(parse_unit_add_sub(tokens)?)),
+        SplitAtOut::NoSplit(tokens) => Ok(UnitConversion::Calculation(parse_unit_or(tokens)?)),
     }
 }
-pub fn parse_unit_add_sub(tokens: Vec<Token>) -> Result<UnitCalculation, String> {
-    match split_at(tokens, vec![Token::Plus, Token::Minus]) {
+/// a minus at the start or after an operator negates, as in `-5 m` or `s^-3`
+fn mark_unary_minus(tokens: Vec<Token>) -> Vec<Token> {
+    let mut marked: Vec<Token> = Vec::with_capacity(tokens.len());
+    for t in tokens {
+        let operand = matches!(
+            marked.last(),
+            Some(
+                Token::Number(_)
+                    | Token::Unit(..)
+                    | Token::Unknown(_)
+                    | Token::CloseBracket
+                    | Token::CloseList
+                    | Token::DateTime(_)
+                    | Token::StringLiteral(_)
+            )
+        );
+        if t == Token::Minus && !operand {
+            marked.push(Token::Function("-".to_string()));
+        } else {
+            marked.push(t);
+        }
+    }
+    marked
+}
+/// `a is what % of b`, `a is what % more than b` or `a is what % less than b`, shown in percent
+fn parse_percent_question(a: Vec<Token>, question: Vec<Token>) -> Result<UnitConversion, String> {
+    let [Token::Unit(_, Some(percent)), relation, b @ ..] = question.as_slice() else {
+        return Err("expected `is what % of`".to_string());
+    };
+    if !is_percent(percent) {
+        return Err("expected `is what % of`".to_string());
+    }
+    let (a, b) = (Box::new(parse_unit_or(a)?), Box::new(parse_unit_or(b.to_vec())?));
+    let calculation = match relation {
+        Token::Of => UnitCalculation::PercentRatio(a, b),
+        Token::MoreThan => UnitCalculation::PercentDifference(a, b, false),
+        Token::LessThan => UnitCalculation::PercentDifference(a, b, true),
+        _ => return Err("expected `of`, `more than` or `less than`".to_string()),
+    };
+    Ok(UnitConversion::Primitive(calculation, percent.clone()))
+}
+fn is_percent(u: &Unit) -> bool {
+    u.abbreviation == "%"
+}
+/// calculations like `15%`, `(5 + 10) percent` or `15% + 10%`, but not `200 + 15%`
+fn is_percentage(c: &UnitCalculation) -> bool {
+    match c {
+        UnitCalculation::Unit(u) => is_percent(u),
+        UnitCalculation::Mult(a, b) | UnitCalculation::ImplMult(a, b) => is_percentage(a) || is_percentage(b),
+        UnitCalculation::Div(a, _)
+        | UnitCalculation::Bracket(a)
+        | UnitCalculation::Function(_, a)
+        | UnitCalculation::AddPercent(a, _)
+        | UnitCalculation::SubtractPercent(a, _) => is_percentage(a),
+        UnitCalculation::Plus(a, b) | UnitCalculation::Minus(a, b) => is_percentage(a) && is_percentage(b),
+        _ => false,
+    }
+}
+/// `ft and in`, `ft+in`, `lb oz` or `h:min:s`: at least two units and nothing else
+fn parse_mixed_units(tokens: &[Token]) -> Option<(Vec<Unit>, bool)> {
+    let mut units = Vec::new();
+    let mut clock = false;
+    for t in tokens {
+        match t {
+            Token::Unit(_, Some(u)) => units.push(u.as_ref().clone()),
+            Token::And | Token::Plus => {}
+            Token::Colon => clock = true,
+            _ => return None,
+        }
+    }
+    (units.len() >= 2).then_some((units, clock))
+}
+pub fn parse_unit_or(tokens: Vec<Token>) -> Result<UnitCalculation, String> {
+    match split_at(tokens, vec![Token::Or]) {
+        SplitAtOut::Split(eq1, _, eq2) => Ok(UnitCalculation::Or(
+            Box::new(parse_unit_or(eq1)?),
+            Box::new(parse_unit_or(eq2)?),
+        )),
+        SplitAtOut::NoSplit(tokens) => parse_unit_and(tokens),
+    }
+}
+pub fn parse_unit_and(tokens: Vec<Token>) -> Result<UnitCalculation, String> {
+    match split_at(tokens, vec![Token::And]) {
+        SplitAtOut::Split(eq1, _, eq2) => Ok(UnitCalculation::And(
+            Box::new(parse_unit_and(eq1)?),
+            Box::new(parse_unit_and(eq2)?),
+        )),
+        SplitAtOut::NoSplit(tokens) => parse_unit_compare(tokens),
+    }
+}
+/// chained comparisons like `1 m < 2 m < 3 m` are collected into a single node
+pub fn parse_unit_compare(tokens: Vec<Token>) -> Result<UnitCalculation, String> {
+    let comparisons = vec![
+        Token::LowerEq,
+        Token::Lower,
+        Token::HigherEq,
+        Token::Higher,
+        Token::Eq,
+        Token::NEq,
+    ];
+    match split_at(tokens, comparisons) {
+        SplitAtOut::Split(eq1, t, eq2) => match parse_unit_compare(eq1)? {
+            UnitCalculation::Compare(a, mut c) => {
+                c.push((t, parse_unit_bit_or(eq2)?));
+                Ok(UnitCalculation::Compare(a, c))
+            }
+            a => Ok(UnitCalculation::Compare(
+                Box::new(a),
+                vec![(t, parse_unit_bit_or(eq2)?)],
+            )),
+        },
+        SplitAtOut::NoSplit(tokens) => parse_unit_bit_or(tokens),
+    }
+}
+pub fn parse_unit_bit_or(tokens: Vec<Token>) -> Result<UnitCalculation, String> {
+    match split_at(tokens, vec![Token::BitOr]) {
+        SplitAtOut::Split(eq1, _, eq2) => Ok(UnitCalculation::BitOr(
+            Box::new(parse_unit_bit_or(eq1)?),
+            Box::new(parse_unit_bit_or(eq2)?),
+        )),
+        SplitAtOut::NoSplit(tokens) => parse_unit_bit_xor(tokens),
+    }
+}
+pub fn parse_unit_bit_xor(tokens: Vec<Token>) -> Result<UnitCalculation, String> {
+    match split_at(tokens, vec![Token::BitXor]) {
+        SplitAtOut::Split(eq1, _, eq2) => Ok(UnitCalculation::BitXor(
+            Box::new(parse_unit_bit_xor(eq1)?),
+            Box::new(parse_unit_bit_xor(eq2)?),
+        )),
+        SplitAtOut::NoSplit(tokens) => parse_unit_bit_and(tokens),
+    }
+}
+pub fn parse_unit_bit_and(tokens: Vec<Token>) -> Result<UnitCalculation, String> {
+    match split_at(tokens, vec![Token::BitAnd]) {
+        SplitAtOut::Split(eq1, _, eq2) => Ok(UnitCalculation::BitAnd(
+            Box::new(parse_unit_bit_and(eq1)?),
+            Box::new(parse_unit_bit_and(eq2)?),
+        )),
+        SplitAtOut::NoSplit(tokens) => parse_unit_shift(tokens),
+    }
+}
+pub fn parse_unit_shift(tokens: Vec<Token>) -> Result<UnitCalculation, String> {
+    match split_at(tokens, vec![Token::ShiftLeft, Token::ShiftRight]) {
         SplitAtOut::Split(eq1, t, eq2) => match t {
-            Token::Plus => Ok(UnitCalculation::Plus(
-                Box::new(parse_unit_add_sub(eq1)?),
-                Box::new(parse_unit_add_sub(eq2)?),
+            Token::ShiftLeft => Ok(UnitCalculation::ShiftLeft(
+                Box::new(parse_unit_shift(eq1)?),
+                Box::new(parse_unit_at(eq2)?),
             )),
-            Token::Minus => Ok(UnitCalculation::Minus(
-                Box::new(parse_unit_add_sub(eq1)?),
-                Box::new(parse_unit_add_sub(eq2)?),
+            _ => Ok(UnitCalculation::ShiftRight(
+                Box::new(parse_unit_shift(eq1)?),
+                Box::new(parse_unit_at(eq2)?),
             )),
-            _ => todo!(),
         },
+        SplitAtOut::NoSplit(tokens) => parse_unit_at(tokens),
+    }
+}
+/// `50 GB at 100 Mbit/s` divides an amount by a rate
+pub fn parse_unit_at(tokens: Vec<Token>) -> Result<UnitCalculation, String> {
+    match split_at(tokens, vec![Token::At]) {
+        SplitAtOut::Split(eq1, _, eq2) => Ok(UnitCalculation::At(
+            Box::new(parse_unit_at(eq1)?),
+            Box::new(parse_unit_add_sub(eq2)?),
+        )),
+        SplitAtOut::NoSplit(tokens) => parse_unit_add_sub(tokens),
+    }
+}
+pub fn parse_unit_add_sub(tokens: Vec<Token>) -> Result<UnitCalculation, String> {
+    match split_at(tokens, vec![Token::Plus, Token::Minus]) {
+        SplitAtOut::Split(eq1, t, eq2) => {
+            let (a, b) = (Box::new(parse_unit_add_sub(eq1)?), Box::new(parse_unit_add_sub(eq2)?));
+            // `200 + 15%` adds 15% of 200, unless both are percentages
+            let percent = is_percentage(&b) && !is_percentage(&a);
+            match (t, percent) {
+                (Token::Plus, true) => Ok(UnitCalculation::AddPercent(a, b)),
+                (Token::Minus, true) => Ok(UnitCalculation::SubtractPercent(a, b)),
+                (Token::Plus, false) => Ok(UnitCalculation::Plus(a, b)),
+                (Token::Minus, false) => Ok(UnitCalculation::Minus(a, b)),
+                _ => todo!(),
+            }
+        }
         SplitAtOut::NoSplit(tokens) => parse_unit_mult_div_1(tokens),
     }
 }
@@ -95,10 +446,10 @@ pub fn parse_unit_mult_div_1(tokens: Vec<Token>) -> Result<UnitCalculation, Stri
     let mut open_brackets = 0;
     for t in tokens.iter() {
         match t {
-            Token::OpenBracket => {
+            Token::OpenBracket | Token::OpenList => {
                 open_brackets += 1;
             }
-            Token::CloseBracket => {
+            Token::CloseBracket | Token::CloseList => {
                 open_brackets -= 1;
             }
             _ => {}
@@ -107,10 +458,10 @@ pub fn parse_unit_mult_div_1(tokens: Vec<Token>) -> Result<UnitCalculation, Stri
     let mut iterator = tokens.iter().enumerate().rev().peekable();
     while let Some((i, t)) = iterator.next() {
         match t {
-            Token::OpenBracket => {
+            Token::OpenBracket | Token::OpenList => {
                 open_brackets += 1;
             }
-            Token::CloseBracket => {
+            Token::CloseBracket | Token::CloseList => {
                 open_brackets -= 1;
             }
             _ => {}
@@ -129,17 +480,22 @@ pub fn parse_unit_mult_div_1(tokens: Vec<Token>) -> Result<UnitCalculation, Stri
                         Box::new(parse_unit_mult_div_1(tokens[i + 1..].to_vec())?),
                     ));
                 }
-                Token::Number(_) | Token::OpenBracket => {
-                    if let Some((_, p)) = iterator.peek() {
-                        match p {
-                            Token::Number(_) | Token::CloseBracket => {
-                                return Ok(UnitCalculation::ImplMult(
-                                    Box::new(parse_unit_mult_div_1(tokens[..i].to_vec())?),
-                                    Box::new(parse_unit_mult_div_1(tokens[i..].to_vec())?),
-                                ));
-                            }
-                            _ => {}
-                        }
+                Token::Of => {
+                    let p = parse_unit_mult_div_1(tokens[..i].to_vec())?;
+                    if !is_percentage(&p) {
+                        return Err("only a percentage can be taken of something".to_string());
+                    }
+                    return Ok(UnitCalculation::PercentOf(
+                        Box::new(p),
+                        Box::new(parse_unit_mult_div_1(tokens[i + 1..].to_vec())?),
+                    ));
+                }
+                Token::Number(_) | Token::OpenBracket | Token::Function(_) => {
+                    if let Some((_, Token::Number(_) | Token::CloseBracket | Token::CloseList)) = iterator.peek() {
+                        return Ok(UnitCalculation::ImplMult(
+                            Box::new(parse_unit_mult_div_1(tokens[..i].to_vec())?),
+                            Box::new(parse_unit_mult_div_1(tokens[i..].to_vec())?),
+                        ));
                     }
                 }
                 _ => {},
@@ -152,10 +508,10 @@ pub fn parse_unit_mult_div_2(tokens: Vec<Token>) -> Result<UnitCalculation, Stri
     let mut open_brackets = 0;
     for t in tokens.iter() {
         match t {
-            Token::OpenBracket => {
+            Token::OpenBracket | Token::OpenList => {
                 open_brackets += 1;
             }
-            Token::CloseBracket => {
+            Token::CloseBracket | Token::CloseList => {
                 open_brackets -= 1;
             }
             _ => {}
@@ -164,40 +520,30 @@ pub fn parse_unit_mult_div_2(tokens: Vec<Token>) -> Result<UnitCalculation, Stri
     let mut iterator = tokens.iter().enumerate().rev().peekable();
     while let Some((i, t)) = iterator.next() {
         match t {
-            Token::OpenBracket => {
+            Token::OpenBracket | Token::OpenList => {
                 open_brackets += 1;
             }
-            Token::CloseBracket => {
+            Token::CloseBracket | Token::CloseList => {
                 open_brackets -= 1;
             }
             _ => {}
         };
         if open_brackets == 0 {
             match t {
-                Token::Unit(_, _) => {
-                    if let Some((_, p)) = iterator.peek() {
-                        match p {
-                            Token::Unit(_, _) | Token::Number(_) | Token::CloseBracket => {
-                                return Ok(UnitCalculation::ImplMult(
-                                    Box::new(parse_unit_mult_div_2(tokens[..i].to_vec())?),
-                                    Box::new(parse_unit_mult_div_2(tokens[i..].to_vec())?),
-                                ));
-                            }
-                            _ => {}
-                        }
+                Token::Unit(_, _) | Token::Unknown(_) => {
+                    if let Some((_, Token::Unit(_, _) | Token::Unknown(_) | Token::Number(_) | Token::CloseBracket | Token::CloseList)) = iterator.peek() {
+                        return Ok(UnitCalculation::ImplMult(
+                            Box::new(parse_unit_mult_div_2(tokens[..i].to_vec())?),
+                            Box::new(parse_unit_mult_div_2(tokens[i..].to_vec())?),
+                        ));
                     }
                 }
-                Token::Number(_) | Token::OpenBracket => {
-                    if let Some((_, p)) = iterator.peek() {
-                        match p {
-                            Token::Unit(_, _) => {
-                                return Ok(UnitCalculation::ImplMult(
-                                    Box::new(parse_unit_mult_div_2(tokens[..i].to_vec())?),
-                                    Box::new(parse_unit_mult_div_2(tokens[i..].to_vec())?),
-                                ));
-                            }
-                            _ => {}
-                        }
+                Token::Number(_) | Token::OpenBracket | Token::Function(_) => {
+                    if let Some((_, Token::Unit(_, _) | Token::Unknown(_))) = iterator.peek() {
+                        return Ok(UnitCalculation::ImplMult(
+                            Box::new(parse_unit_mult_div_2(tokens[..i].to_vec())?),
+                            Box::new(parse_unit_mult_div_2(tokens[i..].to_vec())?),
+                        ));
                     }
                 }
                 _ => {},
@@ -207,6 +553,12 @@ pub fn parse_unit_mult_div_2(tokens: Vec<Token>) -> Result<UnitCalculation, Stri
     parse_unit_pow(tokens)
 }
 pub fn parse_unit_pow(tokens: Vec<Token>) -> Result<UnitCalculation, String> {
+    // -2² is -(2²)
+    if let Some(Token::Function(f)) = tokens.first()
+        && f == "-"
+    {
+        return Ok(UnitCalculation::Function(f.clone(), Box::new(parse_unit_pow(tokens[1..].to_vec())?)));
+    }
     match split_at(tokens, vec![Token::Power]) {
         SplitAtOut::Split(eq1, t, eq2) => match t {
             Token::Power => Ok(UnitCalculation::Pow(
@@ -219,23 +571,50 @@ pub fn parse_unit_pow(tokens: Vec<Token>) -> Result<UnitCalculation, String> {
     }
 }
 pub fn parse_unit_bracket(tokens: Vec<Token>) -> Result<UnitCalculation, String> {
+    if let Some(Token::Function(f)) = tokens.first() {
+        return Ok(UnitCalculation::Function(
+            f.clone(),
+            Box::new(parse_unit_bracket(tokens[1..].to_vec())?),
+        ));
+    }
+    if Some(&Token::OpenList) == tokens.first() && Some(&Token::CloseList) == tokens.last() {
+        let items = split_list(&tokens[1..tokens.len() - 1]);
+        return Ok(UnitCalculation::List(items.into_iter().map(parse_unit_or).collect::<Result<_, _>>()?));
+    }
     if Some(&Token::OpenBracket) == tokens.first() && Some(&Token::CloseBracket) == tokens.last() {
-        Ok(UnitCalculation::Bracket(Box::new(parse_unit_add_sub(tokens[1..tokens.len() - 1].to_vec())?)))
+        Ok(UnitCalculation::Bracket(Box::new(parse_unit_or(tokens[1..tokens.len() - 1].to_vec())?)))
     } else {
         parse_unit_number(tokens)
     }
 }
+/// the items of a list, split at the commas outside of brackets
+fn split_list(tokens: &[Token]) -> Vec<Vec<Token>> {
+    let mut items = vec![Vec::new()];
+    let mut depth = 0;
+    for t in tokens {
+        match t {
+            Token::OpenBracket | Token::OpenList => depth += 1,
+            Token::CloseBracket | Token::CloseList => depth -= 1,
+            _ => {}
+        }
+        match t {
+            Token::Comma if depth == 0 => items.push(Vec::new()),
+            t => items.last_mut().unwrap().push(t.clone()),
+        }
+    }
+    // `[]` and a trailing comma leave no item behind
+    items.retain(|i| !i.is_empty());
+    items
+}
 pub fn parse_unit_number(mut tokens: Vec<Token>) -> Result<UnitCalculation, String> {
     match tokens.len() {
         1 => match tokens.drain(..).next().unwrap() {
-            Token::Number(n) => n.parse::<f64>().map_err(|e| format!("{:?}", e)).map(|n| {
-                UnitCalculation::Number(UnitNumber {
-                    num: n,
-                    units: Vec::new(),
-                })
-            }),
-            Token::Unit(n, _) => Ok(UnitCalculation::Number(n)),
-            t => Err(format!("wrong number token: {}", t.to_string())),
+            Token::Number(n) => UnitNumber::from_decimal(&n).map(UnitCalculation::Number),
+            Token::Unit(_, Some(u)) => Ok(UnitCalculation::Unit(u)),
+            Token::Unit(n, None) => Ok(UnitCalculation::Number(n)),
+            Token::Unknown(name) => Ok(UnitCalculation::Unknown(name)),
+            Token::DateTime(d) => Ok(UnitCalculation::DateTime(d)),
+            t => Err(format!("wrong number token: {}", t)),
         },
         2 => {
             let mut drain = tokens.drain(..);
@@ -244,14 +623,9 @@ pub fn parse_unit_number(mut tokens: Vec<Token>) -> Result<UnitCalculation, Stri
                 drain.next().unwrap(),
             ) {
                 (Token::Dot, Token::Number(n)) => {
-                    format!(".{}",n).parse::<f64>().map_err(|e| format!("{:?}", e)).map(|n| {
-                        UnitCalculation::Number(UnitNumber {
-                            num: n,
-                            units: Vec::new(),
-                        })
-                    })
+                    UnitNumber::from_decimal(&format!(".{}",n)).map(UnitCalculation::Number)
                 }
-                _ => Err(format!("wrong number format!")),
+                _ => Err("wrong number format!".to_string()),
             }
         }
         3 => {
@@ -262,19 +636,52 @@ pub fn parse_unit_number(mut tokens: Vec<Token>) -> Result<UnitCalculation, Stri
                 drain.next().unwrap(),
             ) {
                 (Token::Number(n1),Token::Dot, Token::Number(n2)) => {
-                    format!("{}.{}",n1,n2).parse::<f64>().map_err(|e| format!("{:?}", e)).map(|n| {
-                        UnitCalculation::Number(UnitNumber {
-                            num: n,
-                            units: Vec::new(),
-                        })
-                    })
+                    UnitNumber::from_decimal(&format!("{}.{}",n1,n2)).map(UnitCalculation::Number)
                 }
-                _ => Err(format!("wrong number format!")),
+                (Token::Number(n1), Token::Colon, Token::Number(n2)) => parse_clock_number(&[n1, n2]),
+                _ => Err("wrong number format!".to_string()),
             }
         }
+        5 => match tokens.as_slice() {
+            [Token::Number(n1), Token::Colon, Token::Number(n2), Token::Colon, Token::Number(n3)] => {
+                parse_clock_number(&[n1.clone(), n2.clone(), n3.clone()])
+            }
+            _ => Err(format!("wrong number len: {}", tokens.len())),
+        },
         _ => Err(format!("wrong number len: {}", tokens.len())),
     }
 }
+/// `5:30` is only read as minutes and seconds before a unit, a bare `12:30` is not 12.5
+fn check_clock_numbers(tokens: &[Token]) -> Result<(), String> {
+    let mut i = 0;
+    while i + 2 < tokens.len() {
+        if let [Token::Number(_), Token::Colon, Token::Number(_)] = &tokens[i..i + 3] {
+            let mut end = i + 3;
+            while let [Token::Colon, Token::Number(_), ..] = &tokens[end.min(tokens.len())..] {
+                end += 2;
+            }
+            if !matches!(tokens.get(end), Some(Token::Unit(_, Some(_)))) {
+                return Err("minutes and seconds need a unit, as in 5:30 min/km".to_string());
+            }
+            i = end;
+        }
+        i += 1;
+    }
+    Ok(())
+}
+/// `5:30` is five and a half, in whatever unit follows, as in `5:30 min/km` or `1:02:05 h`
+fn parse_clock_number(parts: &[String]) -> Result<UnitCalculation, String> {
+    let mut total = UnitNumber::from_decimal(&parts[0])?;
+    let mut sixtieths = UnitNumber::from_integer(BigInt::from(1));
+    for part in &parts[1..] {
+        if part.len() != 2 || part.parse::<u32>().is_ok_and(|p| p >= 60) {
+            return Err(format!("{part} is not a valid number of minutes or seconds"));
+        }
+        sixtieths = sixtieths / UnitNumber::from_integer(BigInt::from(60));
+        total = (total + UnitNumber::from_decimal(part)? * sixtieths.clone())?;
+    }
+    Ok(UnitCalculation::Number(total))
+}
 pub enum SplitAtOut {
     Split(Vec<Token>, Token, Vec<Token>),
     NoSplit(Vec<Token>),
@@ -283,10 +690,10 @@ pub fn split_at(tokens: Vec<Token>, split_at_any: Vec<Token>) -> SplitAtOut {
     let mut open_brackets = 0;
     for t in tokens.iter() {
         match t {
-            Token::OpenBracket => {
+            Token::OpenBracket | Token::OpenList => {
                 open_brackets += 1;
             }
-            Token::CloseBracket => {
+            Token::CloseBracket | Token::CloseList => {
                 open_brackets -= 1;
             }
             _ => {}
@@ -294,10 +701,10 @@ pub fn split_at(tokens: Vec<Token>, split_at_any: Vec<Token>) -> SplitAtOut {
     }
     for (i, t) in tokens.iter().enumerate().rev() {
         match t {
-            Token::OpenBracket => {
+            Token::OpenBracket | Token::OpenList => {
                 open_brackets -= 1;
             }
-            Token::CloseBracket => {
+            Token::CloseBracket | Token::CloseList => {
                 open_brackets += 1;
             }
             t => {
@@ -0,0 +1,166 @@
+use num_bigint::BigInt;
+use num_traits::{One, Signed, Zero};
+use regex::Regex;
+
+use crate::unit_calc_parser::{interpreter::shift_amount, parser::UnitCalculation};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Base {
+    Decimal,
+    Hexadecimal,
+    Octal,
+    Binary,
+}
+impl Base {
+    pub const ALL: [Base; 4] = [Base::Decimal, Base::Hexadecimal, Base::Octal, Base::Binary];
+    fn radix(self) -> u32 {
+        match self {
+            Base::Decimal => 10,
+            Base::Hexadecimal => 16,
+            Base::Octal => 8,
+            Base::Binary => 2,
+        }
+    }
+    fn prefix(self) -> &'static str {
+        match self {
+            Base::Decimal => "",
+            Base::Hexadecimal => "0x",
+            Base::Octal => "0o",
+            Base::Binary => "0b",
+        }
+    }
+}
+/// a fixed integer width like `u8` or `i32`
+#[derive(Clone, Copy, PartialEq)]
+pub struct Width {
+    pub bits: u32,
+    pub signed: bool,
+}
+/// what `in hex`, `as u8` and similar switches at the end of a query asked for
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct IntegerMode {
+    pub base: Option<Base>,
+    pub width: Option<Width>,
+}
+
+/// strips trailing `in hex`, `to dec`, `as u8`, ... and returns what they asked for
+pub fn parse_integer_switch(query: &str) -> (String, Option<IntegerMode>) {
+    let re = Regex::new(
+        r"^(.*?)\s+(?:in|to|as|=>|->)\s+(hex|hexadecimal|dec|decimal|oct|octal|bin|binary|[ui](?:8|16|32|64))\s*$",
+    )
+    .unwrap();
+    let mut query = query.to_string();
+    let mut mode: Option<IntegerMode> = None;
+    while let Some(captures) = re.captures(&query) {
+        let m = mode.get_or_insert_default();
+        match &captures[2] {
+            "hex" | "hexadecimal" => m.base = m.base.or(Some(Base::Hexadecimal)),
+            "dec" | "decimal" => m.base = m.base.or(Some(Base::Decimal)),
+            "oct" | "octal" => m.base = m.base.or(Some(Base::Octal)),
+            "bin" | "binary" => m.base = m.base.or(Some(Base::Binary)),
+            w => {
+                m.width = m.width.or(Some(Width {
+                    bits: w[1..].parse().unwrap(),
+                    signed: w.starts_with('i'),
+                }))
+            }
+        }
+        query = captures[1].to_string();
+    }
+    (query, mode)
+}
+/// `0xFF`, `0o17` or `0b1011`
+pub fn parse_radix_literal(s: &str) -> Option<BigInt> {
+    let base = match s.get(..2)? {
+        "0x" | "0X" => Base::Hexadecimal,
+        "0o" | "0O" => Base::Octal,
+        "0b" | "0B" => Base::Binary,
+        _ => return None,
+    };
+    let digits = &s[2..];
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(base.radix())) {
+        return None;
+    }
+    BigInt::parse_bytes(digits.as_bytes(), base.radix())
+}
+/// wraps around like an integer of the given width would on overflow
+pub fn wrap(i: &BigInt, width: Width) -> BigInt {
+    let modulus = BigInt::one() << width.bits;
+    let mut wrapped = ((i % &modulus) + &modulus) % &modulus;
+    if width.signed && wrapped >= (&modulus >> 1) {
+        wrapped -= modulus;
+    }
+    wrapped
+}
+/// evaluates `c` like a program with integers of `width` would, so `(200 + 100) / 2 as u8` is 22
+pub fn execute_wrapped(c: &UnitCalculation, width: Width) -> Result<BigInt, String> {
+    let w = |c: &UnitCalculation| execute_wrapped(c, width);
+    let i = match c {
+        UnitCalculation::Bracket(a) => w(a)?,
+        UnitCalculation::Plus(a, b) => w(a)? + w(b)?,
+        UnitCalculation::Minus(a, b) => w(a)? - w(b)?,
+        UnitCalculation::Mult(a, b) | UnitCalculation::ImplMult(a, b) => w(a)? * w(b)?,
+        // truncating toward zero like integer division in most languages, so `7 / 2` is 3
+        UnitCalculation::Div(a, b) => {
+            let divisor = w(b)?;
+            if divisor.is_zero() {
+                return Err("division by zero".to_string());
+            }
+            w(a)? / divisor
+        }
+        UnitCalculation::BitAnd(a, b) => w(a)? & w(b)?,
+        UnitCalculation::BitOr(a, b) => w(a)? | w(b)?,
+        UnitCalculation::BitXor(a, b) => w(a)? ^ w(b)?,
+        UnitCalculation::ShiftLeft(a, b) => w(a)? << shift_amount(b)?,
+        UnitCalculation::ShiftRight(a, b) => w(a)? >> shift_amount(b)?,
+        UnitCalculation::Function(f, a) if f == "~" => !w(a)?,
+        UnitCalculation::Function(f, a) if f == "-" => -w(a)?,
+        c => c.execute()?.to_integer()?,
+    };
+    Ok(wrap(&i, width))
+}
+/// negative numbers are shown in two's complement if there is a width
+pub fn format_integer(i: &BigInt, base: Base, width: Option<Width>) -> String {
+    if base == Base::Decimal {
+        return i.to_string();
+    }
+    match width {
+        Some(w) if i.is_negative() => {
+            let unsigned = wrap(i, Width { bits: w.bits, signed: false });
+            format!("{}{}", base.prefix(), digits(&unsigned, base))
+        }
+        _ if i.is_negative() => format!("-{}{}", base.prefix(), digits(&-i, base)),
+        _ => format!("{}{}", base.prefix(), digits(i, base)),
+    }
+}
+fn digits(i: &BigInt, base: Base) -> String {
+    i.to_str_radix(base.radix()).to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unit_calc_parser::{
+        environment::Environment,
+        lexer::{get_units, lex},
+        parser::{UnitConversion, parse_unit_conversion},
+    };
+
+    fn wrapped(query: &str, width: &str) -> Result<BigInt, String> {
+        let (query, mode) = parse_integer_switch(&format!("{query} as {width}"));
+        let units = get_units();
+        let tokens = lex(query, &units, &Environment::default()).unwrap();
+        match parse_unit_conversion(tokens)? {
+            UnitConversion::Calculation(c) => execute_wrapped(&c, mode.unwrap().width.unwrap()),
+            _ => panic!("not a calculation"),
+        }
+    }
+
+    #[test]
+    fn integer_division() {
+        assert_eq!(wrapped("7 / 2", "u8"), Ok(BigInt::from(3)));
+        assert_eq!(wrapped("(0 - 7) / 2", "i8"), Ok(BigInt::from(-3)));
+        assert_eq!(wrapped("(200 + 100) / 2", "u8"), Ok(BigInt::from(22)));
+        assert_eq!(wrapped("1 / 0", "u8"), Err("division by zero".to_string()));
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::BTreeMap;
+
+use num_rational::Rational64;
+use num_traits::Signed;
+
+use crate::unit_calc_parser::{
+    lexer::Unit,
+    unit_number_parser::{MetricBaseUnit, UnitExp, UnitNumber, with_exponent},
+};
+
+/// prefixes that are correct but rarely seen, e.g. deciseconds
+const UNCOMMON_PREFIXES: &[&str] = &["centi", "deci", "deca", "hecto", "yocto", "zepto", "zetta", "yotta"];
+/// units that are divided by as a square root, as in noise densities like volts per √hertz
+const ROOT_UNITS: &[&str] = &["hertz"];
+/// the units centi is common on, unlike centihertz
+const CENTI_UNITS: &[&str] = &["meter", "liter"];
+/// long times are counted in hours and days rather than kiloseconds
+const LARGE_PREFIXES: &[&str] = &["kilo", "mega", "giga", "tera", "peta", "exa", "zetta", "yotta"];
+/// the priority of units like percent and the constants, which are never chosen for a result
+pub const NEVER_CHOSEN: f32 = -1e30;
+
+// scores are counted in significant digits: a penalty of 1 makes a unit as hard to read as one
+// more digit in the number
+/// how much harder a product of two units is to read than a single unit
+const PAIR_PENALTY: f64 = 1.5;
+/// any prefix is a little harder to read than the plain unit
+const PREFIX_PENALTY: f64 = 0.5;
+/// for each power of an uncommon prefix, so decimeters² lose against square meters
+const UNCOMMON_PREFIX_PENALTY: f64 = 2.0;
+/// kiloseconds are read as roughly a quarter hour only after a moment's thought
+const LARGE_TIME_PREFIX_PENALTY: f64 = 3.0;
+/// a single unit to a negative power, like hertz⁻¹, nearly always has a better reading
+const RECIPROCAL_PENALTY: f64 = 4.0;
+/// on top of that for a reciprocal time like minutes⁻¹, which is just a frequency
+const RECIPROCAL_TIME_PENALTY: f64 = 2.0;
+/// roots of units are only meant as a last resort
+const ROOT_PENALTY: f64 = 5.0;
+/// for a binary prefix on a decimal input or the other way round
+const MIXED_PREFIX_PENALTY: f64 = 2.0;
+/// for a product using a unit of the input, as much as the pair costs
+const INPUT_UNIT_BONUS: f64 = 1.5;
+/// for a product using a unit of a dimension the input didn't mention
+const FOREIGN_UNIT_PENALTY: f64 = 1.0;
+/// for a unit other than the input's one for the same dimension
+const OTHER_UNIT_PENALTY: f64 = 2.0;
+
+/// one way to show a quantity, as a product of units like kilowatt hours or meters per second
+#[derive(Clone)]
+pub struct Simplification {
+    pub factors: Vec<(Unit, Rational64)>,
+    /// the quantity divided by the units, without a dimension
+    pub num: UnitNumber,
+    pub score: f64,
+}
+
+type Dimension = Vec<(MetricBaseUnit, Rational64)>;
+
+fn dimension(n: &UnitNumber) -> Dimension {
+    n.cleaned().units.into_iter().map(|u| (u.unit, u.exp)).collect()
+}
+/// the product of the units, e.g. `kilowatt hours`, `meters per second²` or `square meters`
+pub fn describe(factors: &[(Unit, Rational64)], singular: bool) -> String {
+    if let [(u, exponent)] = factors {
+        let name = if singular { &u.name } else { &u.plural };
+        return with_exponent(name.clone(), *exponent);
+    }
+    let numerators = factors.iter().filter(|(_, e)| *e > Rational64::from_integer(0)).collect::<Vec<_>>();
+    let mut parts = numerators
+        .iter()
+        .enumerate()
+        .map(|(i, (u, e))| {
+            // only the last word is plural: kilowatt hours
+            let name = if singular || i + 1 < numerators.len() { &u.name } else { &u.plural };
+            with_exponent(name.clone(), *e)
+        })
+        .collect::<Vec<String>>();
+    for (u, e) in factors.iter().filter(|(_, e)| *e < Rational64::from_integer(0)) {
+        parts.push(format!("per {}", with_exponent(u.name.clone(), -*e)));
+    }
+    parts.join(" ")
+}
+/// numerators first, the more complex unit before the simpler one and time last, as in newton meters
+fn display_order(factors: &mut [(Unit, Rational64)]) {
+    factors.sort_by_key(|(u, e)| {
+        let dimension = dimension(&u.si);
+        (
+            *e < Rational64::from_integer(0),
+            std::cmp::Reverse(dimension.len()),
+            dimension.iter().any(|(b, _)| *b == MetricBaseUnit::Second),
+        )
+    });
+}
+/// how readable `factors` make the quantity, `None` for zero
+fn score(
+    factors: &[(Unit, Rational64)],
+    num: &UnitNumber,
+    units: &[Unit],
+    input_units: &[Unit],
+) -> Option<f64> {
+    if num.num == 0.0 || !num.num.is_finite() {
+        return None;
+    }
+    // few significant digits and a number between 1 and 1000 read best
+    let scientific = format!("{:.5e}", num.num.abs());
+    let (mantissa, exponent) = scientific.split_once('e')?;
+    let significant = mantissa.trim_end_matches('0').trim_end_matches('.').replace('.', "").len();
+    let exponent: i32 = exponent.parse().ok()?;
+    let magnitude = if exponent >= 3 { exponent - 2 } else { (-exponent).max(0) };
+    // far off numbers are hard to read anyway, so they are not worth an exotic prefix
+    let magnitude = magnitude.min(3) as f64 * 1.5 + (magnitude - 3).max(0) as f64 * 0.02;
+    let mut score = -(significant as f64) - magnitude - 0.1 * exponent.clamp(0, 2) as f64;
+    if factors.len() > 1 {
+        score -= PAIR_PENALTY;
+    }
+    let uses_binary = input_units.iter().any(|u| u.binary);
+    let seconds = UnitNumber {
+        num: 1.0,
+        units: vec![UnitExp { exp: Rational64::from_integer(1), unit: MetricBaseUnit::Second }],
+        exact: None,
+    };
+    for (unit, exponent) in factors {
+        score += unit.priority as f64;
+        if *exponent != Rational64::from_integer(1) {
+            score -= 0.1;
+        }
+        // ∛nanoliters are never what anyone wants to read
+        if !exponent.is_integer() {
+            score -= ROOT_PENALTY;
+        }
+        if *exponent < Rational64::from_integer(0) {
+            // per hour is fine in a product, hertz⁻¹ on its own is not
+            score -= if factors.len() == 1 { RECIPROCAL_PENALTY } else { 0.1 };
+            if factors.len() == 1 && unit.si.addable(seconds.clone()) {
+                score -= RECIPROCAL_TIME_PENALTY;
+            }
+        }
+        let prefix = unit.name.strip_suffix(&unit.base_name).unwrap_or("");
+        if !prefix.is_empty() {
+            score -= PREFIX_PENALTY;
+        }
+        // prefixes on units outside SI, like picoparsecs, are as rare unless the input used them
+        let foreign = unit.priority < 0.0 && !input_units.iter().any(|i| i.base_name == unit.base_name);
+        // unless the input used the prefix itself, as in `2 dl`
+        let uncommon = UNCOMMON_PREFIXES.contains(&prefix)
+            && !(prefix == "centi" && CENTI_UNITS.contains(&unit.base_name.as_str()))
+            && !input_units.iter().any(|i| i.name == unit.name);
+        if uncommon || (!prefix.is_empty() && foreign) {
+            score -= UNCOMMON_PREFIX_PENALTY * exponent.abs().ceil().to_integer() as f64;
+        }
+        if LARGE_PREFIXES.contains(&prefix) && unit.si.addable(seconds.clone()) {
+            score -= LARGE_TIME_PREFIX_PENALTY;
+        }
+        // stick to binary or decimal prefixes, whichever the input used
+        if !prefix.is_empty()
+            && unit.binary != uses_binary
+            && units.iter().any(|b| b.binary && b.base_name == unit.base_name)
+        {
+            score -= MIXED_PREFIX_PENALTY;
+        }
+        // products read best in the units of the input, e.g. km/h for `5 km / 2 h` rather than W/N
+        if factors.len() > 1 {
+            if input_units.iter().any(|i| i.name == unit.name) {
+                score += INPUT_UNIT_BONUS;
+            } else if !input_units.iter().any(|i| i.si.addable(unit.si.clone())) {
+                score -= FOREIGN_UNIT_PENALTY;
+            }
+        }
+        // and to the units the input used for this dimension, e.g. bytes instead of bits
+        if input_units.iter().any(|i| i.si.addable(unit.si.clone()))
+            && !input_units.iter().any(|i| i.base_name == unit.base_name)
+        {
+            score -= OTHER_UNIT_PENALTY;
+        }
+    }
+    Some(score)
+}
+/// a unit of a single base dimension like meters or hours, rather than joules
+fn is_base(u: &Unit) -> bool {
+    matches!(u.si.cleaned().units.as_slice(), [e] if e.exp == Rational64::from_integer(1))
+}
+/// whether a base dimension of `a` is mostly cancelled by `b`, as in millimeters² per liter,
+/// which is just a reciprocal length
+fn cancels(a: &Dimension, b: &Dimension) -> bool {
+    a.iter().any(|(base, ea)| {
+        b.iter().any(|(other, eb)| {
+            other == base && ea.signum() != eb.signum() && (ea + eb).abs() < ea.abs().min(eb.abs())
+        })
+    })
+}
+/// unprefixed pairs like watt hours or meters per second that have the dimension of `un`;
+/// where a single unit exists, like joules for watt hours, only products of units of the input
+fn pairs(un: &UnitNumber, units: &[Unit], input_units: &[Unit]) -> Vec<Vec<(Unit, Rational64)>> {
+    // hertz would only make joule hertz out of watts
+    let base_units = units
+        .iter()
+        .filter(|u| u.name == u.base_name && u.si.units.iter().any(|e| e.exp > Rational64::from_integer(0)))
+        .collect::<Vec<&Unit>>();
+    // km/h and mph don't stand in for meters per second
+    let has_single_unit =
+        base_units.iter().any(|u| u.priority >= 0.0 && un.addable(u.si.cleaned()));
+    let in_input = |u: &Unit| input_units.iter().any(|i| i.base_name == u.base_name);
+    // without a hint from the input only SI units are combined, not zeptoelectronvolt days
+    let coherent = |u: &Unit| {
+        u.priority >= 0.0 && units.iter().any(|p| p.base_name == u.base_name && p.name != p.base_name)
+    };
+    let mut by_dimension: BTreeMap<Dimension, Vec<&Unit>> = BTreeMap::new();
+    for u in &base_units {
+        by_dimension.entry(dimension(&u.si)).or_default().push(u);
+    }
+    let target = dimension(un);
+    let mut pairs: Vec<Vec<(Unit, Rational64)>> = Vec::new();
+    for a in &base_units {
+        // squares only of simple units, as in meters per second²
+        let exponents: &[i64] = if is_base(a) { &[1, -1, 2, -2] } else { &[1, -1] };
+        for &a_exponent in exponents {
+            let mut rest = target.clone();
+            for (b, e) in dimension(&a.si) {
+                match rest.iter_mut().find(|(r, _)| *r == b) {
+                    Some((_, r)) => *r -= e * a_exponent,
+                    None => rest.push((b, -e * a_exponent)),
+                }
+            }
+            rest.retain(|(_, e)| *e != Rational64::from_integer(0));
+            rest.sort();
+            if rest.is_empty() {
+                continue;
+            }
+            for b in units.iter().filter(|u| ROOT_UNITS.contains(&u.name.as_str()) && u.base_name != a.base_name) {
+                let exponent = Rational64::new(-1, 2);
+                let mut root = dimension(&b.si).into_iter().map(|(d, e)| (d, e * exponent)).collect::<Dimension>();
+                root.sort();
+                if root == rest && a_exponent > 0 && (in_input(a) || coherent(a)) {
+                    pairs.push(vec![((*a).clone(), Rational64::from_integer(a_exponent)), (b.clone(), exponent)]);
+                }
+            }
+            for b_exponent in [1, -1] {
+                let key = rest.iter().map(|(b, e)| (b.clone(), e * b_exponent)).collect::<Dimension>();
+                for b in by_dimension.get(&key).into_iter().flatten() {
+                    let product = a_exponent > 0 && b_exponent > 0;
+                    let guided = in_input(a) || in_input(b);
+                    let scaled = |u: &Unit, exponent: i64| {
+                        dimension(&u.si).into_iter().map(|(d, e)| (d, e * exponent)).collect::<Dimension>()
+                    };
+                    if b.base_name == a.base_name
+                        || (a_exponent < 0 && b_exponent < 0)
+                        || cancels(&scaled(a, a_exponent), &scaled(b, b_exponent))
+                        || (has_single_unit && (!product || !in_input(a) || !in_input(b)))
+                        || [a, b].iter().any(|u| !in_input(u) && !coherent(u))
+                        || (!guided && !is_base(a) && !is_base(b))
+                    {
+                        continue;
+                    }
+                    let mut pair = vec![
+                        ((*a).clone(), Rational64::from_integer(a_exponent)),
+                        ((*b).clone(), Rational64::from_integer(b_exponent)),
+                    ];
+                    display_order(&mut pair);
+                    let same = |p: &Vec<(Unit, Rational64)>| {
+                        p.iter().zip(&pair).all(|((x, xe), (y, ye))| x.name == y.name && xe == ye)
+                    };
+                    if !pairs.iter().any(same) {
+                        pairs.push(pair);
+                    }
+                }
+            }
+        }
+    }
+    pairs
+}
+/// every way to show `un` as one unit or a product of two, the most readable first
+pub fn simplify(un: &UnitNumber, units: &[Unit], input_units: &[Unit]) -> Vec<Simplification> {
+    if un.units.is_empty() {
+        return Vec::new();
+    }
+    let units = units.iter().filter(|u| u.priority > NEVER_CHOSEN).cloned().collect::<Vec<Unit>>();
+    let units = units.as_slice();
+    let mut candidates: Vec<Vec<(Unit, Rational64)>> = Vec::new();
+    for unit in units {
+        // hertz⁻¹ is just seconds
+        let reciprocal = unit.si.units.iter().all(|e| e.exp < Rational64::from_integer(0));
+        // kilometers per hour² would read as km/h²
+        let compound = unit.si.cleaned().units.len() > 1;
+        if let Some(log) = un.log(&unit.si.cleaned())
+            && !(reciprocal && log < Rational64::from_integer(0))
+            && !(compound && log != Rational64::from_integer(1))
+        {
+            candidates.push(vec![(unit.clone(), log)]);
+        }
+    }
+    // roots of units only if nothing else fits, like for √m
+    if candidates.iter().any(|c| c[0].1.is_integer()) {
+        candidates.retain(|c| c[0].1.is_integer());
+    }
+    // the prefix goes on the first unit: kilowatt hours, kilometers per hour
+    for pair in pairs(un, units, input_units) {
+        for prefixed in units.iter().filter(|u| u.base_name == pair[0].0.base_name) {
+            let mut candidate = pair.clone();
+            candidate[0].0 = prefixed.clone();
+            candidates.push(candidate);
+        }
+    }
+    let mut simplifications = candidates
+        .into_iter()
+        .filter_map(|factors| {
+            let unit = factors.iter().fold(UnitNumber::from_decimal("1").ok()?, |acc, (u, e)| {
+                acc * u.si.cleaned().pow_rational(*e)
+            });
+            let num = un.clone() / unit;
+            if !num.units.is_empty() {
+                return None;
+            }
+            let score = score(&factors, &num, units, input_units)?;
+            Some(Simplification { factors, num, score })
+        })
+        .collect::<Vec<Simplification>>();
+    simplifications.sort_by(|a, b| b.score.total_cmp(&a.score));
+    simplifications
+}
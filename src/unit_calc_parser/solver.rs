@@ -0,0 +1,198 @@
+use num_bigint::BigInt;
+use num_rational::{BigRational, Rational64};
+use num_traits::One;
+
+use crate::unit_calc_parser::{
+    parser::UnitCalculation,
+    unit_calculator::to_simple_fraction,
+    unit_number_parser::UnitNumber,
+};
+
+/// coefficients by power of the unknown, `None` where a power does not occur
+type Polynomial = Vec<Option<UnitNumber>>;
+
+/// the unknown in `left = right` and every value of it that solves the equation
+pub fn solve(left: &UnitCalculation, right: &UnitCalculation) -> Result<(String, Vec<UnitNumber>), String> {
+    let mut occurrences = left.unknowns();
+    occurrences.extend(right.unknowns());
+    let Some(name) = occurrences.first().map(|n| n.to_string()) else {
+        return Err("there is no unknown to solve for".to_string());
+    };
+    if occurrences.iter().any(|n| *n != name) {
+        return Err("only equations with a single unknown can be solved".to_string());
+    }
+    // a single occurrence can be isolated, which also works for `100 km / t = 50 km/h`
+    let solutions = if occurrences.len() == 1 {
+        match left.unknowns().is_empty() {
+            true => isolate(right, left.execute()?)?,
+            false => isolate(left, right.execute()?)?,
+        }
+    } else {
+        solve_polynomial(subtract(polynomial(left)?, polynomial(right)?)?)?
+    };
+    Ok((name, solutions))
+}
+/// undoes the operations around the unknown in `c`, given that `c` equals `value`
+fn isolate(c: &UnitCalculation, value: UnitNumber) -> Result<Vec<UnitNumber>, String> {
+    let has_unknown = |c: &UnitCalculation| !c.unknowns().is_empty();
+    match c {
+        UnitCalculation::Unknown(_) => Ok(vec![value]),
+        UnitCalculation::Bracket(a) => isolate(a, value),
+        UnitCalculation::Function(f, a) => match f.as_str() {
+            "-" => isolate(a, -value),
+            "√" => isolate(a, value.pow_i64(2)),
+            "∛" => isolate(a, value.pow_i64(3)),
+            f => Err(format!("cannot solve for the argument of {f}")),
+        },
+        UnitCalculation::Plus(a, b) if has_unknown(a) => isolate(a, (value - b.execute()?)?),
+        UnitCalculation::Plus(a, b) => isolate(b, (value - a.execute()?)?),
+        UnitCalculation::Minus(a, b) if has_unknown(a) => isolate(a, (value + b.execute()?)?),
+        UnitCalculation::Minus(a, b) => isolate(b, (a.execute()? - value)?),
+        UnitCalculation::Mult(a, b) | UnitCalculation::ImplMult(a, b) if has_unknown(a) => {
+            isolate(a, divide(value, b.execute()?)?)
+        }
+        UnitCalculation::Mult(a, b) | UnitCalculation::ImplMult(a, b) => isolate(b, divide(value, a.execute()?)?),
+        UnitCalculation::Div(a, b) if has_unknown(a) => {
+            let divisor = b.execute()?;
+            if divisor.num == 0.0 {
+                return Err("no value solves this equation".to_string());
+            }
+            isolate(a, value * divisor)
+        }
+        UnitCalculation::Div(a, b) => isolate(b, divide(a.execute()?, value)?),
+        UnitCalculation::Pow(a, b) if !has_unknown(b) => {
+            let exponent = to_simple_fraction(b.execute()?.to_exponent()?)
+                .filter(|e| *e != Rational64::from_integer(0))
+                .ok_or("only simple powers of the unknown can be solved".to_string())?;
+            let root = value.pow_rational(Rational64::from_integer(1) / exponent);
+            if root.num.is_nan() {
+                return Err("the equation has no real solution".to_string());
+            }
+            // x² = 16 m² has two solutions
+            let mut roots = vec![root.clone()];
+            if exponent.is_integer() && exponent.to_integer() % 2 == 0 && root.num != 0.0 {
+                roots.push(-root);
+            }
+            let mut solutions = Vec::new();
+            for r in roots {
+                solutions.extend(isolate(a, r)?);
+            }
+            Ok(solutions)
+        }
+        c => Err(format!("cannot solve for the unknown in {}", c)),
+    }
+}
+/// `value / divisor`, where a zero divisor leaves the unknown either free or impossible
+fn divide(value: UnitNumber, divisor: UnitNumber) -> Result<UnitNumber, String> {
+    match (divisor.num == 0.0, value.num == 0.0) {
+        (true, true) => Err("every value solves this equation".to_string()),
+        (true, false) => Err("no value solves this equation".to_string()),
+        (false, _) => Ok(value / divisor),
+    }
+}
+fn one() -> UnitNumber {
+    UnitNumber::from_integer(BigInt::one())
+}
+/// `c` as a polynomial in the unknown, for unknowns that occur several times like in `x² + 2x = 8`
+fn polynomial(c: &UnitCalculation) -> Result<Polynomial, String> {
+    if c.unknowns().is_empty() {
+        return Ok(vec![Some(c.execute()?)]);
+    }
+    match c {
+        UnitCalculation::Unknown(_) => Ok(vec![None, Some(one())]),
+        UnitCalculation::Bracket(a) => polynomial(a),
+        UnitCalculation::Function(f, a) if f == "-" => Ok(negate(polynomial(a)?)),
+        UnitCalculation::Plus(a, b) => add(polynomial(a)?, polynomial(b)?),
+        UnitCalculation::Minus(a, b) => subtract(polynomial(a)?, polynomial(b)?),
+        UnitCalculation::Mult(a, b) | UnitCalculation::ImplMult(a, b) => multiply(&polynomial(a)?, &polynomial(b)?),
+        UnitCalculation::Div(a, b) if b.unknowns().is_empty() => {
+            let divisor = b.execute()?;
+            Ok(polynomial(a)?.into_iter().map(|t| t.map(|t| t / divisor.clone())).collect())
+        }
+        UnitCalculation::Pow(a, b) if b.unknowns().is_empty() => {
+            let exponent = b.execute()?.to_i64()?;
+            if !(0..=4).contains(&exponent) {
+                return Err("only small whole powers of the unknown can be solved".to_string());
+            }
+            let base = polynomial(a)?;
+            let mut power = vec![Some(one())];
+            for _ in 0..exponent {
+                power = multiply(&power, &base)?;
+            }
+            Ok(power)
+        }
+        c => Err(format!("cannot solve for the unknown in {}", c)),
+    }
+}
+fn negate(p: Polynomial) -> Polynomial {
+    p.into_iter().map(|t| t.map(|t| -t)).collect()
+}
+fn add(a: Polynomial, b: Polynomial) -> Result<Polynomial, String> {
+    let mut sum = a;
+    sum.resize(sum.len().max(b.len()), None);
+    for (i, t) in b.into_iter().enumerate() {
+        sum[i] = match (sum[i].take(), t) {
+            (Some(x), Some(y)) => Some((x + y)?),
+            (x, y) => x.or(y),
+        };
+    }
+    Ok(sum)
+}
+fn subtract(a: Polynomial, b: Polynomial) -> Result<Polynomial, String> {
+    add(a, negate(b))
+}
+fn multiply(a: &Polynomial, b: &Polynomial) -> Result<Polynomial, String> {
+    let mut product: Polynomial = vec![None; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            if let (Some(x), Some(y)) = (x, y) {
+                product = add(product, [vec![None; i + j], vec![Some(x.clone() * y.clone())]].concat())?;
+            }
+        }
+    }
+    Ok(product)
+}
+/// the real roots of a linear or quadratic polynomial that equals zero
+fn solve_polynomial(mut p: Polynomial) -> Result<Vec<UnitNumber>, String> {
+    while p.last().is_some_and(|t| t.as_ref().is_none_or(|t| t.num == 0.0)) {
+        p.pop();
+    }
+    let zero = |t: &Option<UnitNumber>| t.clone().unwrap_or_else(|| UnitNumber::from_integer(BigInt::from(0)));
+    match p.as_slice() {
+        [] => Err("every value solves this equation".to_string()),
+        [_] => Err("no value solves this equation".to_string()),
+        [c0, Some(c1)] => Ok(vec![-(zero(c0) / c1.clone())]),
+        [c0, c1, Some(c2)] => {
+            // the unit of the unknown follows from c2·x² having the dimension of c0, or of c1·x
+            let dimension = match (c0, c1) {
+                (Some(c0), _) => (c0.clone() / c2.clone()).pow_rational(Rational64::new(1, 2)).units,
+                (None, Some(c1)) => (c1.clone() / c2.clone()).units,
+                (None, None) => Vec::new(),
+            };
+            let unit = UnitNumber { num: 1.0, units: dimension, exact: Some(BigRational::one()) }.cleaned();
+            let leading = c2.clone() * unit.clone().pow_i64(2);
+            // with x = u·unit and divided by the leading term, the coefficients lose their units
+            let normalized = |t: &Option<UnitNumber>, power: i64| {
+                let t = zero(t) * unit.clone().pow_i64(power) / leading.clone();
+                match t.num == 0.0 || t.cleaned().units.is_empty() {
+                    true => Ok(t.num),
+                    false => Err("the terms of the equation have different dimensions".to_string()),
+                }
+            };
+            let (b, c) = (normalized(c1, 1)?, normalized(c0, 0)?);
+            let discriminant = b * b - 4.0 * c;
+            if discriminant < 0.0 {
+                return Err("the equation has no real solution".to_string());
+            }
+            let mut roots = vec![(-b + discriminant.sqrt()) / 2.0];
+            if discriminant > 0.0 {
+                roots.push((-b - discriminant.sqrt()) / 2.0);
+            }
+            Ok(roots
+                .into_iter()
+                .map(|r| UnitNumber { num: r, units: unit.units.clone(), exact: None })
+                .collect())
+        }
+        _ => Err("only linear and quadratic equations can be solved".to_string()),
+    }
+}
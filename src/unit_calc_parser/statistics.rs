@@ -0,0 +1,70 @@
+use std::cmp::Ordering;
+
+use num_bigint::BigInt;
+use num_rational::Rational64;
+
+use crate::unit_calc_parser::unit_number_parser::UnitNumber;
+
+/// functions that turn a list into a single value, in the order their results are shown
+pub const AGGREGATES: &[&str] = &["sum", "mean", "median", "min", "max", "stddev"];
+
+fn count(n: usize) -> UnitNumber {
+    UnitNumber::from_integer(BigInt::from(n))
+}
+fn sum(values: &[UnitNumber]) -> Result<UnitNumber, String> {
+    let mut values = values.iter().cloned();
+    let first = values.next().ok_or("the list is empty".to_string())?;
+    // adding checks that the dimensions agree
+    values.try_fold(first, |acc, v| acc + v)
+}
+fn mean(values: &[UnitNumber]) -> Result<UnitNumber, String> {
+    Ok(sum(values)? / count(values.len()))
+}
+/// the values from the smallest to the largest, if they can be compared
+fn sorted(values: &[UnitNumber]) -> Result<Vec<UnitNumber>, String> {
+    let mut sorted = values.to_vec();
+    let mut error = None;
+    sorted.sort_by(|a, b| {
+        a.compare(b).unwrap_or_else(|e| {
+            error.get_or_insert(e);
+            Ordering::Equal
+        })
+    });
+    match error {
+        Some(e) => Err(e),
+        None if sorted.is_empty() => Err("the list is empty".to_string()),
+        None => Ok(sorted),
+    }
+}
+fn median(values: &[UnitNumber]) -> Result<UnitNumber, String> {
+    let sorted = sorted(values)?;
+    let middle = sorted.len() / 2;
+    if sorted.len() % 2 == 1 {
+        return Ok(sorted[middle].clone());
+    }
+    mean(&sorted[middle - 1..=middle])
+}
+/// the sample standard deviation, as spreadsheets compute it
+fn stddev(values: &[UnitNumber]) -> Result<UnitNumber, String> {
+    if values.len() < 2 {
+        return Err("the standard deviation needs at least two values".to_string());
+    }
+    let mean = mean(values)?;
+    let squares = values
+        .iter()
+        .map(|v| Ok((v.clone() - mean.clone())?.pow_i64(2)))
+        .collect::<Result<Vec<UnitNumber>, String>>()?;
+    Ok((sum(&squares)? / count(values.len() - 1)).pow_rational(Rational64::new(1, 2)))
+}
+/// applies one of the `AGGREGATES` to a list
+pub fn aggregate(f: &str, values: &[UnitNumber]) -> Result<UnitNumber, String> {
+    match f {
+        "sum" => sum(values),
+        "mean" => mean(values),
+        "median" => median(values),
+        "min" => sorted(values).map(|s| s[0].clone()),
+        "max" => sorted(values).map(|s| s[s.len() - 1].clone()),
+        "stddev" => stddev(values),
+        f => Err(format!("unknown function: {f}")),
+    }
+}
@@ -1,8 +1,41 @@
-use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::{
+    cmp::Ordering,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
 
-use crate::unit_calc_parser::unit_number_parser::{UnitExp, UnitNumber};
+use num_bigint::BigInt;
+use num_rational::{BigRational, Rational64};
+use num_traits::{FromPrimitive, Signed, ToPrimitive, Zero};
+
+use crate::unit_calc_parser::{
+    exact::{limit, parse_decimal},
+    programmer::parse_radix_literal,
+    unit_number_parser::{UnitExp, UnitNumber},
+};
 
 impl UnitNumber {
+    /// a dimensionless number, exact when `s` is a plain decimal
+    pub fn from_decimal(s: &str) -> Result<Self, String> {
+        if let Some(i) = parse_radix_literal(s) {
+            return Ok(Self::from_integer(i));
+        }
+        Ok(UnitNumber {
+            num: s.parse::<f64>().map_err(|e| format!("{:?}", e))?,
+            units: Vec::new(),
+            exact: parse_decimal(s),
+        })
+    }
+    /// multiplies by an exact factor, e.g. for prefixes
+    pub fn scaled(&self, factor: &BigRational) -> Self {
+        UnitNumber {
+            num: self.num * factor.to_f64().unwrap_or(f64::NAN),
+            units: self.units.clone(),
+            exact: self.exact.as_ref().map(|e| e * factor),
+        }
+    }
+    pub fn scaled_pow10(&self, exp: i32) -> Self {
+        self.scaled(&BigRational::from_integer(10.into()).pow(exp))
+    }
     pub fn clean(&mut self) {
         let mut nu: Vec<UnitExp> = Vec::new();
         for u in self.units.drain(..) {
@@ -13,7 +46,7 @@ impl UnitNumber {
             }
         }
         self.units = nu;
-        self.units.retain(|a| a.exp != 0);
+        self.units.retain(|a| !a.exp.is_zero());
         self.units.sort_by(|a, b| a.unit.cmp(&b.unit));
     }
     pub fn cleaned(&self) -> Self {
@@ -31,31 +64,159 @@ impl UnitNumber {
             })
             .collect::<Vec<UnitExp>>();
         self.num = self.num.powi(exp as i32);
+        self.exact = match self.exact {
+            Some(e) if !(e.is_zero() && exp < 0) && exp.abs() <= i32::MAX as i64 => {
+                limit_pow(&e, exp as i32)
+            }
+            _ => None,
+        };
+        self
+    }
+    pub fn pow_rational(mut self, exp: Rational64) -> Self {
+        if exp.is_integer() {
+            return self.pow_i64(exp.to_integer());
+        }
+        self.units = self
+            .units
+            .iter()
+            .map(|x| UnitExp {
+                exp: x.exp * exp,
+                unit: x.unit.clone(),
+            })
+            .collect::<Vec<UnitExp>>();
+        self.exact = self
+            .exact
+            .and_then(|e| exact_root(&e, *exp.denom()))
+            .and_then(|e| limit_pow(&e, *exp.numer() as i32));
+        let odd_root = exp.denom() % 2 == 1;
+        let odd_power = exp.numer() % 2 != 0;
+        let exp = *exp.numer() as f64 / *exp.denom() as f64;
+        // odd roots of negative numbers are real
+        if self.num < 0.0 && odd_root {
+            self.num = (-self.num).powf(exp);
+            if odd_power {
+                self.num = -self.num;
+            }
+        } else {
+            self.num = self.num.powf(exp);
+        }
         self
     }
+    /// raises to a real exponent; numbers with units only accept simple fractions
+    pub fn pow(self, exp: f64) -> Result<Self, String> {
+        if let Some(exp) = to_simple_fraction(exp) {
+            if self.num < 0.0 && exp.denom() % 2 == 0 {
+                return Err("even roots of negative numbers are not real".to_string());
+            }
+            Ok(self.pow_rational(exp))
+        } else if self.units.is_empty() {
+            let num = self.num.powf(exp);
+            if num.is_nan() {
+                return Err("the power of a negative number is not real".to_string());
+            }
+            Ok(UnitNumber {
+                num,
+                units: Vec::new(),
+                exact: None,
+            })
+        } else {
+            Err("only fractional exponents are allowed for numbers with units!".to_string())
+        }
+    }
     pub fn to_i64(&self) -> Result<i64, String> {
-        if self.units.len() == 0 && (self.num.round() - self.num).abs() < 1e-10 {
+        if self.units.is_empty() && (self.num.round() - self.num).abs() < 1e-10 {
             Ok(self.num.round() as i64)
         } else {
             Err("only integer exponents without numbers are allowed!".to_string())
         }
     }
+    pub fn from_integer(i: BigInt) -> Self {
+        UnitNumber {
+            num: i.to_f64().unwrap_or(f64::NAN),
+            units: Vec::new(),
+            exact: Some(BigRational::from_integer(i)),
+        }
+    }
+    /// the exact integer value, for bitwise operations and fixed widths
+    pub fn to_integer(&self) -> Result<BigInt, String> {
+        if !self.units.is_empty() {
+            return Err("expected an integer without units!".to_string());
+        }
+        match &self.exact {
+            Some(e) if e.is_integer() => Ok(e.to_integer()),
+            Some(_) => Err("expected an integer!".to_string()),
+            None if self.num.fract() == 0.0 => {
+                BigInt::from_f64(self.num).ok_or("expected a finite integer!".to_string())
+            }
+            None => Err("expected an integer!".to_string()),
+        }
+    }
+    pub fn to_exponent(&self) -> Result<f64, String> {
+        if self.units.is_empty() {
+            Ok(self.num)
+        } else {
+            Err("exponents must not have units!".to_string())
+        }
+    }
     pub fn addable(&self, other: Self) -> bool {
         self.units.len() == other.units.len()
             && self.units.iter().zip(other.units).all(|(a, b)| *a == b)
     }
-    pub fn log(&self, smaller: &Self) -> Option<i64> {
-        if self.units.len() == smaller.units.len() {
-            if self.units.len() > 0 {
-                let log=self.units[0].exp/smaller.units[0].exp;
-                if self.units.iter().zip(smaller.units.clone()).all(|(a,b)| a.unit==b.unit&&b.exp*log==a.exp){
-                    return Some(log);
-                }
+    /// compares two quantities of the same dimension, treating tiny relative differences as equal
+    pub fn compare(&self, other: &Self) -> Result<Ordering, String> {
+        if !self.addable(other.clone()) {
+            return Err("quantities with different units cannot be compared!".to_string());
+        }
+        if (self.num - other.num).abs() <= 1e-9 * self.num.abs().max(other.num.abs()) {
+            Ok(Ordering::Equal)
+        } else {
+            self.num
+                .partial_cmp(&other.num)
+                .ok_or("NaN cannot be compared!".to_string())
+        }
+    }
+    pub fn log(&self, smaller: &Self) -> Option<Rational64> {
+        if self.units.len() == smaller.units.len()
+            && !self.units.is_empty()
+        {
+            let log=self.units[0].exp/smaller.units[0].exp;
+            if self.units.iter().zip(smaller.units.clone()).all(|(a,b)| a.unit==b.unit&&b.exp*log==a.exp){
+                return Some(log);
             }
         }
         None
     }
 }
+/// raises an exact value to a power unless the result gets unreasonably large
+fn limit_pow(e: &BigRational, exp: i32) -> Option<BigRational> {
+    // bits of the result are roughly bits of the base times the exponent
+    let bits = e.numer().bits() + e.denom().bits();
+    if bits.saturating_mul(exp.unsigned_abs() as u64) > 100_000 {
+        return None;
+    }
+    limit(e.pow(exp))
+}
+/// the exact `n`th root, if the value is a perfect power
+fn exact_root(e: &BigRational, n: i64) -> Option<BigRational> {
+    if e.is_negative() && n % 2 == 0 {
+        return None;
+    }
+    let n = u32::try_from(n).ok()?;
+    let numer = e.numer().nth_root(n);
+    let denom = e.denom().nth_root(n);
+    let root = BigRational::new(numer, denom);
+    if &root.pow(n as i32) == e { Some(root) } else { None }
+}
+/// finds the fraction with a small denominator that `x` was most likely meant to be
+pub fn to_simple_fraction(x: f64) -> Option<Rational64> {
+    for denom in 1..=12 {
+        let numer = (x * denom as f64).round();
+        if (numer / denom as f64 - x).abs() < 1e-9 {
+            return Some(Rational64::new(numer as i64, denom));
+        }
+    }
+    None
+}
 impl Add for UnitNumber {
     type Output = Result<Self, String>;
     fn add(mut self, rhs: Self) -> Self::Output {
@@ -63,6 +224,10 @@ impl Add for UnitNumber {
             && self.units.iter().zip(rhs.units).all(|(a, b)| *a == b)
         {
             self.num += rhs.num;
+            self.exact = match (self.exact, rhs.exact) {
+                (Some(a), Some(b)) => Some(a + b),
+                _ => None,
+            };
             Ok(self)
         } else {
             Err("non-matching units cannot be added!".to_string())
@@ -73,6 +238,7 @@ impl Neg for UnitNumber {
     type Output = Self;
     fn neg(mut self) -> Self::Output {
         self.num = -self.num;
+        self.exact = self.exact.map(|e| -e);
         self
     }
 }
@@ -87,6 +253,10 @@ impl Mul for UnitNumber {
     fn mul(mut self, rhs: Self) -> Self::Output {
         self.units.extend(rhs.units);
         self.num *= rhs.num;
+        self.exact = match (self.exact, rhs.exact) {
+            (Some(a), Some(b)) => limit(a * b),
+            _ => None,
+        };
         self.clean();
         self
     }
@@ -0,0 +1,188 @@
+use num_rational::{BigRational, Rational64};
+use num_traits::One;
+use serde::Deserialize;
+
+use crate::unit_calc_parser::{
+    environment::Environment,
+    lexer::{Unit, lex},
+    unit_registry::UnitRegistry,
+    parser::parse_unit_or,
+    unit_number_parser::{MetricBaseUnit, UnitExp, UnitNumber},
+};
+
+/// the units that ship with btsrch
+pub const BUILTIN_UNITS: &str = include_str!("../../units.json");
+/// physical constants, loaded like units so they can be used by symbol or name
+pub const CONSTANTS: &str = include_str!("../../constants.json");
+/// units added by the user, read from the working directory at startup
+pub const USER_UNITS_FILE: &str = "user_units.json";
+
+/// one entry of a units file; `definition` may only use units defined above it
+#[derive(Deserialize)]
+pub struct UnitDefinition {
+    pub name: String,
+    pub plural: String,
+    pub abbreviation: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// an expression of other units, e.g. `"kg m/s^2"`
+    pub definition: Option<String>,
+    /// makes the unit the base of a dimension instead, e.g. `"m"` or `"story point"`
+    pub base: Option<MetricBaseUnit>,
+    #[serde(default)]
+    pub si_prefixes: bool,
+    /// adds kibi, mebi, ... variants, for data units
+    #[serde(default)]
+    pub iec_prefixes: bool,
+    #[serde(default)]
+    pub priority: f32,
+    /// for rounded definitions like π that must not be treated as exact
+    #[serde(default)]
+    pub inexact: bool,
+    /// where the value comes from, shown for constants
+    #[serde(default)]
+    pub source: Option<String>,
+}
+impl UnitDefinition {
+    pub fn into_units(self, units: &UnitRegistry) -> Result<Vec<Unit>, String> {
+        let mut si = match (self.base, self.definition) {
+            (Some(base), None) => UnitNumber {
+                num: 1.0,
+                units: vec![UnitExp {
+                    exp: Rational64::from_integer(1),
+                    unit: base,
+                }],
+                exact: Some(BigRational::one()),
+            },
+            (None, Some(definition)) => {
+                let tokens = lex(definition.clone(), units, &Environment::default())
+                    .ok_or(format!("could not lex \"{definition}\""))?;
+                parse_unit_or(tokens)?.execute()?
+            }
+            _ => return Err("needs either a base or a definition".to_string()),
+        };
+        if self.inexact {
+            si.exact = None;
+        }
+        let aliases = self.aliases.clone();
+        let unit = Unit {
+            base_name: self.name.clone(),
+            binary: false,
+            name: self.name,
+            plural: self.plural,
+            abbreviation: self.abbreviation,
+            valid_names: self.aliases,
+            si,
+            priority: self.priority,
+        }
+        .create();
+        let mut units = Vec::new();
+        if self.iec_prefixes {
+            units.extend(unit.clone().add_iec_prefixes(&aliases));
+        }
+        if self.si_prefixes {
+            units.extend(unit.add_si_prefixes(&aliases));
+        } else {
+            units.push(unit);
+        }
+        Ok(units)
+    }
+}
+/// parses a units file and appends its units; broken entries are reported and skipped
+pub fn load_unit_definitions(source: &str, units: &mut UnitRegistry) -> Result<(), String> {
+    let definitions: Vec<UnitDefinition> =
+        serde_json::from_str(source).map_err(|e| e.to_string())?;
+    for d in definitions {
+        let name = d.name.clone();
+        match d.into_units(units) {
+            Ok(u) => units.extend(u),
+            Err(e) => eprintln!("skipping unit {name}: {e}"),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::unit_calc_parser::{
+        environment::Environment,
+        lexer::lex,
+        parser::parse_unit_conversion,
+        unit_number_parser::UnitNumber,
+        unit_registry::UnitRegistry,
+        value::Value,
+    };
+
+    fn si(query: &str, units: &UnitRegistry) -> UnitNumber {
+        let tokens = lex(query.to_string(), units, &Environment::default()).unwrap();
+        match parse_unit_conversion(tokens).unwrap().execute().unwrap().0 {
+            Value::Number(n) => n,
+            _ => panic!("{query} is not a number"),
+        }
+    }
+
+    /// one conversion for every derived, imperial and angle unit in units.json, each against a
+    /// reference value rather than the unit's own definition
+    #[test]
+    fn conversions() {
+        let units = crate::unit_calc_parser::lexer::get_units();
+        let table = [
+            ("1 rad", "1"),
+            ("1 rad", "57.29577951308232 deg"),
+            ("1 sr", "1"),
+            ("60 arcmin", "1 deg"),
+            ("60 arcsec", "1 arcmin"),
+            ("60 rpm", "1 Hz"),
+            ("1 Bq", "1 Hz"),
+            ("1 mA h", "3.6 C"),
+            ("1 kW h", "3.6e6 J"),
+            ("1 Ω", "1 kg m^2/(s^3 A^2)"),
+            ("1 S", "1 s^3 A^2/(kg m^2)"),
+            ("1 F", "1 s^4 A^2/(kg m^2)"),
+            ("1 H", "1 kg m^2/(s^2 A^2)"),
+            ("1 Wb", "1 kg m^2/(s^2 A)"),
+            ("1 T", "1 kg/(s^2 A)"),
+            ("1 Pa", "1 kg/(m s^2)"),
+            ("1 bar", "14.503773773020923 psi"),
+            ("1 atm", "1.01325 bar"),
+            ("1 Torr", "133.32236842105263 Pa"),
+            ("1 mmHg", "1.0000001424663214 Torr"),
+            ("1 Gy", "1 m^2/s^2"),
+            ("1 Sv", "1 m^2/s^2"),
+            ("1 lm", "1 cd"),
+            ("1 lx", "1 cd/m^2"),
+            ("3.6 kcal", "4.184 W h"),
+            ("1 BTU", "0.2930710701722222 W h"),
+            ("1 kbps", "125 B/s"),
+            ("1 m²", "10000 cm^2"),
+            ("1 m³", "1000 L"),
+            ("1 ha", "0.01 km^2"),
+            ("36 km/h", "10 m/s"),
+            ("1 t", "1 Mg"),
+            ("1 Å", "0.1 nm"),
+            ("1 au", "92955807.27302553 mi"),
+            ("1 pc", "206264.80624709636 au"),
+            ("1 nmi", "1.1507794480235425 mi"),
+            ("1 mph", "1.609344 km/h"),
+            ("1 kn", "1.852 km/h"),
+            ("1 gal", "4 qt"),
+            ("1 cup", "236.5882365 mL"),
+            ("1 fl oz", "29.5735295625 mL"),
+            ("1 tbsp", "14.78676478125 mL"),
+            ("1 tsp", "4.92892159375 mL"),
+            ("1 psi", "6894.757293168361 Pa"),
+            ("1 kgf", "2.2046226218487757 lbf"),
+            ("1 hp", "550 lbf ft/s"),
+            ("1 sqmi", "640 ac"),
+            ("1 sqin", "6.4516 cm^2"),
+            ("1 st", "6.35029318 kg"),
+            ("1 Ci", "3.7e10 Hz"),
+            ("1 rem", "0.01 m^2/s^2"),
+        ];
+        for (a, b) in table {
+            let (x, y) = (si(a, &units), si(b, &units));
+            assert!(x.addable(y.clone()), "{a} and {b} have different dimensions");
+            assert!((x.num / y.num - 1.0).abs() < 1e-9, "{a} is {} but {b} is {}", x.num, y.num);
+        }
+    }
+}
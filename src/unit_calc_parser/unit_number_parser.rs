@@ -1,23 +1,45 @@
-#[derive(Clone, PartialEq)]
+use std::fmt;
+
+use num_rational::{BigRational, Rational64};
+use num_traits::Signed;
+use serde::{Deserialize, Serialize};
+
+use crate::unit_calc_parser::exact::to_terminating_decimal;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct UnitNumber {
     pub num: f64,
     pub units: Vec<UnitExp>,
+    /// the same value as `num` without rounding errors, as long as it is known exactly
+    #[serde(default)]
+    pub exact: Option<BigRational>,
 }
-impl ToString for UnitNumber {
-    fn to_string(&self) -> String {
+impl fmt::Display for UnitNumber {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        // short exact decimals avoid float artifacts like 0.30000000000000004
+        let num = match self.exact.as_ref().and_then(to_terminating_decimal) {
+            Some(d) if d.len() <= 40 => d,
+            _ => format!("{}", self.num),
+        };
+        formatter.write_str(&self.to_string_with(num))
+    }
+}
+impl UnitNumber {
+    /// formats the units around an already formatted number
+    pub fn to_string_with(&self, num: String) -> String {
         let upos = self
             .units
             .clone()
             .iter()
-            .filter(|a| a.exp > 0)
-            .map(|a| unit_exp_to_superscript_exp(a))
+            .filter(|a| a.exp.is_positive())
+            .map(unit_exp_to_superscript_exp)
             .collect::<Vec<String>>()
             .join("");
         let udiv = self
             .units
             .clone()
             .iter()
-            .filter(|a| a.exp < 0)
+            .filter(|a| a.exp.is_negative())
             .map(|a| {
                 unit_exp_to_superscript_exp(&UnitExp {
                     exp: -a.exp,
@@ -27,46 +49,52 @@ impl ToString for UnitNumber {
             .collect::<Vec<String>>()
             .join("");
         if self.num==1.0{
-            if upos.len() == 0 {
-                if udiv.len() == 0 {
-                    format!("")
+            if upos.is_empty() {
+                if udiv.is_empty() {
+                    num
                 } else {
                     format!("1/{udiv}")
                 }
             } else {
-                if udiv.len() == 0 {
-                    format!("{upos}")
+                if udiv.is_empty() {
+                    upos
                 } else {
                     format!("{upos}/{udiv}")
                 }
             }
         }else{
-            if upos.len() == 0 {
-                if udiv.len() == 0 {
-                    format!("{}",self.num)
+            if upos.is_empty() {
+                if udiv.is_empty() {
+                    num.to_string()
                 } else {
-                    format!("{} 1/{udiv}",self.num)
+                    format!("{} 1/{udiv}",num)
                 }
             } else {
-                if udiv.len() == 0 {
-                    format!("{} {upos}",self.num)
+                if udiv.is_empty() {
+                    format!("{} {upos}",num)
                 } else {
-                    format!("{} {upos}/{udiv}",self.num)
+                    format!("{} {upos}/{udiv}",num)
                 }
             }
         }
     }
 }
 pub fn unit_exp_to_superscript_exp(input: &UnitExp) -> String {
-    format!(
-        "{}{}",
-        input.unit.to_string(),
-        if input.exp == 1 {
-            String::new()
-        } else {
-            superscript(input.exp.to_string())
-        }
-    )
+    with_exponent(input.unit.to_string(), input.exp)
+}
+/// appends the exponent to a unit name, using roots for ½ and ⅓
+pub fn with_exponent(unit: String, exp: Rational64) -> String {
+    if exp == Rational64::from_integer(1) {
+        unit
+    } else if exp.is_integer() {
+        format!("{}{}", unit, superscript(exp.to_integer().to_string()))
+    } else if exp == Rational64::new(1, 2) {
+        format!("√{}", unit)
+    } else if exp == Rational64::new(1, 3) {
+        format!("∛{}", unit)
+    } else {
+        format!("{}^({})", unit, exp)
+    }
 }
 pub fn superscript(input: String) -> String {
     input
@@ -87,17 +115,18 @@ pub fn superscript(input: String) -> String {
         })
         .collect::<String>()
 }
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct UnitExp {
-    pub exp: i64,
+    pub exp: Rational64,
     pub unit: MetricBaseUnit,
 }
-impl ToString for UnitExp {
-    fn to_string(&self) -> String {
-        format!("{}^{}", self.unit.to_string(), self.exp)
+impl fmt::Display for UnitExp {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}^{}", self.unit, self.exp)
     }
 }
-#[derive(Clone, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Clone, PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
 pub enum MetricBaseUnit {
     Meter,
     Gramm,
@@ -107,10 +136,32 @@ pub enum MetricBaseUnit {
     Mole,
     Candela,
     Byte,
+    /// a dimension declared in a units file, like story points
+    Custom(String),
+}
+impl From<String> for MetricBaseUnit {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "m" => MetricBaseUnit::Meter,
+            "g" => MetricBaseUnit::Gramm,
+            "s" => MetricBaseUnit::Second,
+            "A" => MetricBaseUnit::Ampere,
+            "K" | "°K" => MetricBaseUnit::Kelvin,
+            "mol" => MetricBaseUnit::Mole,
+            "cd" => MetricBaseUnit::Candela,
+            "B" => MetricBaseUnit::Byte,
+            _ => MetricBaseUnit::Custom(s),
+        }
+    }
+}
+impl From<MetricBaseUnit> for String {
+    fn from(u: MetricBaseUnit) -> Self {
+        u.to_string()
+    }
 }
-impl ToString for MetricBaseUnit {
-    fn to_string(&self) -> String {
-        match self {
+impl fmt::Display for MetricBaseUnit {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(match self {
             MetricBaseUnit::Meter => "m",
             MetricBaseUnit::Gramm => "g",
             MetricBaseUnit::Second => "s",
@@ -119,7 +170,7 @@ impl ToString for MetricBaseUnit {
             MetricBaseUnit::Mole => "mol",
             MetricBaseUnit::Candela => "cd",
             MetricBaseUnit::Byte => "B",
-        }
-        .to_string()
+            MetricBaseUnit::Custom(s) => s,
+        })
     }
 }
@@ -0,0 +1,48 @@
+use std::{collections::HashMap, ops::Deref};
+
+use crate::unit_calc_parser::lexer::Unit;
+
+/// all known units with an index over their names, built once at startup
+#[derive(Clone, Default)]
+pub struct UnitRegistry {
+    units: Vec<Unit>,
+    names: HashMap<String, Vec<usize>>,
+    /// in chars, so the lexer knows when to stop looking for longer names
+    longest_name: usize,
+}
+impl UnitRegistry {
+    pub fn push(&mut self, unit: Unit) {
+        for name in &unit.valid_names {
+            let ids = self.names.entry(name.clone()).or_default();
+            if !ids.contains(&self.units.len()) {
+                ids.push(self.units.len());
+            }
+            self.longest_name = self.longest_name.max(name.chars().count());
+        }
+        self.units.push(unit);
+    }
+    /// every unit called `name`, in the order they were added
+    pub fn lookup(&self, name: &str) -> impl Iterator<Item = &Unit> {
+        self.names
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(|i| &self.units[*i])
+    }
+    pub fn longest_name(&self) -> usize {
+        self.longest_name
+    }
+}
+impl Extend<Unit> for UnitRegistry {
+    fn extend<T: IntoIterator<Item = Unit>>(&mut self, iter: T) {
+        for unit in iter {
+            self.push(unit);
+        }
+    }
+}
+impl Deref for UnitRegistry {
+    type Target = [Unit];
+    fn deref(&self) -> &[Unit] {
+        &self.units
+    }
+}
@@ -0,0 +1,43 @@
+use std::fmt;
+
+use jiff::Zoned;
+
+use crate::unit_calc_parser::{datetime::format_datetime, unit_number_parser::UnitNumber};
+
+#[derive(Clone, PartialEq)]
+pub enum Value {
+    Number(UnitNumber),
+    Bool(bool),
+    DateTime(Zoned),
+    List(Vec<UnitNumber>),
+}
+impl Value {
+    pub fn into_number(self) -> Result<UnitNumber, String> {
+        match self {
+            Value::Number(n) => Ok(n),
+            Value::Bool(_) => Err("expected a number, got a boolean!".to_string()),
+            Value::DateTime(_) => Err("expected a number, got a date!".to_string()),
+            Value::List(_) => Err("expected a number, got a list!".to_string()),
+        }
+    }
+    pub fn into_bool(self) -> Result<bool, String> {
+        match self {
+            Value::Bool(b) => Ok(b),
+            Value::Number(_) => Err("expected a boolean, got a number!".to_string()),
+            Value::DateTime(_) => Err("expected a boolean, got a date!".to_string()),
+            Value::List(_) => Err("expected a boolean, got a list!".to_string()),
+        }
+    }
+}
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::DateTime(d) => write!(f, "{}", format_datetime(d)),
+            Value::List(l) => {
+                write!(f, "[{}]", l.iter().map(|n| n.to_string()).collect::<Vec<String>>().join(", "))
+            }
+        }
+    }
+}